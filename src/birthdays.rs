@@ -0,0 +1,59 @@
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use uuid::Uuid;
+use crate::contact::Contact;
+use crate::name_format::FIRST_LAST;
+
+/// The next birthday of a contact.
+#[derive(Serialize, Debug)]
+pub struct Birthday {
+    pub identifier: Uuid,
+    pub name: String,
+    /// The day of the next birthday.
+    pub date: NaiveDate,
+    /// Days until the birthday, 0 being today.
+    pub days: i64,
+    /// The age the contact turns, when the birth year is known.
+    pub age: Option<i32>,
+}
+
+/// The birthdays of living contacts in the next `days` days, from `today`
+/// included, soonest first. Contacts born on February 29 celebrate on
+/// February 28 in other years.
+pub fn upcoming(contacts: &[Contact], today: NaiveDate, days: u32) -> Vec<Birthday> {
+    let mut birthdays: Vec<Birthday> = contacts
+        .iter()
+        .filter(|c| c.identity.is_alive)
+        .filter_map(|contact| {
+            let birth = contact.identity.birth_date.as_ref()?;
+            let (month, day) = (u32::from(birth.month?), u32::from(birth.day?));
+            let date = next_occurrence(today, month, day)?;
+            let days_left = (date - today).num_days();
+            if days_left > i64::from(days) {
+                return None;
+            }
+            Some(Birthday {
+                identifier: contact.identifier,
+                name: contact.format_name(&FIRST_LAST),
+                date,
+                days: days_left,
+                age: birth.year.map(|year| date.year() - year),
+            })
+        })
+        .collect();
+
+    birthdays.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+    birthdays
+}
+
+/// The first day on or after `today` that is the anniversary of `month`/`day`.
+fn next_occurrence(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let in_year = |year: i32| {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .or_else(|| ((month, day) == (2, 29)).then(|| NaiveDate::from_ymd_opt(year, 2, 28)).flatten())
+    };
+    match in_year(today.year())? {
+        date if date >= today => Some(date),
+        _ => in_year(today.year() + 1),
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::IsTerminal;
+use crate::contact::Contact;
+use crate::group::Group;
+use crate::models::{Gender, TuppData};
+use crate::name_format::{FIRST_LAST, FULL_NAME};
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Cards are never drawn wider than this, even on wide terminals.
+const MAX_WIDTH: usize = 80;
+
+/// Render a contact as a sectioned card with aligned labels, resolving group
+/// and linked contact identifiers to names. Colors are only used on a
+/// terminal, and never when `NO_COLOR` is set.
+pub fn render_card(contact: &Contact, data: &TuppData) -> String {
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+    let width = terminal_size::terminal_size()
+        .map(|(terminal_size::Width(w), _)| w as usize)
+        .unwrap_or(MAX_WIDTH)
+        .clamp(40, MAX_WIDTH);
+
+    let card = Card { color, width };
+    let identity = &contact.identity;
+    let mut out = String::new();
+
+    let name = contact.format_name(&FULL_NAME);
+    out.push_str(&card.paint(BOLD, &name));
+    out.push('\n');
+
+    let mut section = Vec::new();
+    if let Some(gender) = &identity.gender {
+        section.push(("Gender".to_string(), match gender {
+            Gender::Male => "Male",
+            Gender::Female => "Female",
+            Gender::NonBinary => "Non-binary",
+        }.to_string()));
+    }
+    section.push(("Alive".to_string(), if identity.is_alive { "Yes" } else { "No" }.to_string()));
+    if let Some(date) = &identity.birth_date {
+        section.push(("Birth date".to_string(), date.to_string()));
+    }
+    if let Some(location) = &identity.birth_location {
+        section.push(("Birth location".to_string(), location.to_string()));
+    }
+    let birth_name = [&identity.birth_first_name, &identity.birth_middle_name, &identity.birth_last_name]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !birth_name.is_empty() {
+        section.push(("Birth name".to_string(), birth_name));
+    }
+    if let Some(date) = &identity.anniversary {
+        section.push(("Anniversary".to_string(), date.to_string()));
+    }
+    if !identity.is_alive {
+        if let Some(date) = &identity.death_date {
+            section.push(("Death date".to_string(), date.to_string()));
+        }
+        if let Some(location) = &identity.death_location {
+            section.push(("Death location".to_string(), location.to_string()));
+        }
+    }
+    card.section(&mut out, "Identity", &section);
+
+    let mut section = Vec::new();
+    for email in contact.emails.iter().flatten() {
+        section.push((
+            format!("Email ({})", email.label.as_deref().unwrap_or("default")),
+            email.address.clone().unwrap_or_default(),
+        ));
+    }
+    for phone in contact.phones.iter().flatten() {
+        section.push((
+            format!("Phone ({})", phone.label.as_deref().unwrap_or("default")),
+            format!("+{} {}", phone.country_code, phone.number),
+        ));
+    }
+    if let Some(address) = &contact.address {
+        section.push((
+            format!("Address ({})", address.label.as_deref().unwrap_or("default")),
+            address.to_string(),
+        ));
+    }
+    card.section(&mut out, "Contact", &section);
+
+    let section: Vec<(String, String)> = contact.socials.iter().flatten()
+        .map(|s| (s.network.clone(), s.username.clone().unwrap_or_default()))
+        .collect();
+    card.section(&mut out, "Social", &section);
+
+    let section: Vec<(String, String)> = contact.links.iter().flatten()
+        .map(|link| {
+            let target = data.contacts.iter()
+                .find(|c| c.identifier == link.target)
+                .map(|c| c.format_name(&FIRST_LAST))
+                .unwrap_or_else(|| format!("unknown contact {}", link.target));
+            (link.relation.to_string(), target)
+        })
+        .collect();
+    card.section(&mut out, "Relationships", &section);
+
+    let mut groups: Vec<String> = contact.groups.iter().flatten()
+        .map(|id| {
+            Group::find_group_by_id_recursive(&data.groups, id)
+                .map(|g| g.name.clone())
+                .unwrap_or_else(|| format!("unknown group {}", id))
+        })
+        .collect();
+    groups.sort();
+    if !groups.is_empty() {
+        card.section(&mut out, "Groups", &[(String::new(), groups.join(", "))]);
+    }
+
+    out.push('\n');
+    out.push_str(&card.paint(DIM, &contact.identifier.to_string()));
+    out
+}
+
+struct Card {
+    color: bool,
+    width: usize,
+}
+
+impl Card {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Append a titled section, with labels padded to the same width and
+    /// values wrapped to the card width. Empty sections are skipped.
+    fn section(&self, out: &mut String, title: &str, rows: &[(String, String)]) {
+        if rows.is_empty() {
+            return;
+        }
+
+        let rule = "─".repeat(self.width.saturating_sub(title.chars().count() + 4));
+        out.push('\n');
+        out.push_str(&self.paint(CYAN, &format!("── {} {}", title, rule)));
+        out.push('\n');
+
+        let label_width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+        let indent = if label_width == 0 { 2 } else { label_width + 4 };
+        for (label, value) in rows {
+            let padding = " ".repeat(label_width - label.chars().count());
+            let lines = wrap(value, self.width.saturating_sub(indent).max(10));
+            for (i, line) in lines.iter().enumerate() {
+                if i == 0 && label_width > 0 {
+                    out.push_str(&format!("  {}{}  {}\n", self.paint(DIM, label), padding, line));
+                } else {
+                    out.push_str(&format!("{}{}\n", " ".repeat(indent), line));
+                }
+            }
+        }
+    }
+}
+
+/// Break text into lines of at most `width` characters, on spaces when possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        // Words longer than a line are split
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        if word.is_empty() {
+            continue;
+        }
+        let word: String = word.into_iter().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
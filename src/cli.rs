@@ -1,10 +1,24 @@
 use clap::{Parser, Subcommand};
+use crate::merge::Side;
+use crate::sort::SortKey;
+use crate::output::OutputFormat;
+use crate::import::OnDuplicate;
+use crate::formats::{ExportFormat, ImportFormat};
+use crate::qr::{QrField, QrKind};
 
 #[derive(Parser, Debug)]
 #[clap(name = "tupp", version = "1.3.1", author = "mtripnaux & gaiadrd")]
 pub struct Cli {
     #[clap(subcommand)]
     pub command: Commands,
+
+    /// Print results of list, show, find and birthdays commands in a machine-readable format.
+    #[clap(long, global = true, value_enum)]
+    pub output: Option<OutputFormat>,
+
+    /// Fields to output, as serde paths or short names (e.g. "first,last,emails.default").
+    #[clap(long, global = true, value_delimiter = ',')]
+    pub columns: Vec<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -23,8 +37,20 @@ pub enum Commands {
 
     /// Export contacts to a specified file.
     Export {
-        /// The path to the export file.
+        /// The path to the export file, or directory for HTML and Markdown.
         path: String,
+
+        /// The format of the export file.
+        #[clap(short, long, value_enum, default_value = "json")]
+        format: ExportFormat,
+
+        /// For CSV, a file of `Header = field` lines choosing the columns.
+        #[clap(long)]
+        mapping: Option<String>,
+
+        /// For LDIF, the DN the entries are written under.
+        #[clap(long, value_name = "DN")]
+        base_dn: Option<String>,
     },
 
     /// Initialize the contact list (clears all data).
@@ -39,6 +65,100 @@ pub enum Commands {
         path: Option<String>,
     },
 
+    /// Report contacts that are likely duplicates of each other.
+    Dedupe {
+        /// Only report pairs scoring at least this similarity (0 to 1).
+        #[clap(short, long, default_value_t = 0.5)]
+        min_score: f64,
+
+        /// Print the report as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Import contacts from a file or another address book.
+    Import {
+        /// The file or directory to import.
+        path: String,
+
+        /// The format of the file, guessed from its extension or content by default.
+        #[clap(short, long, value_enum)]
+        format: Option<ImportFormat>,
+
+        /// For CSV, a file of `Header = field` lines mapping columns to contact
+        /// fields. Google Contacts and Outlook headers are detected otherwise.
+        #[clap(long)]
+        mapping: Option<String>,
+
+        /// Show what would be imported without saving anything.
+        #[clap(long)]
+        dry_run: bool,
+
+        /// What to do with contacts that look like existing ones.
+        #[clap(long, value_enum, default_value = "skip")]
+        on_duplicate: OnDuplicate,
+
+        /// Minimum similarity (0 to 1) for a contact to count as a duplicate.
+        #[clap(long, default_value_t = 0.5)]
+        min_score: f64,
+    },
+
+    /// Show the contacts and groups that differ between two data files, field by field.
+    Diff {
+        /// The older data file.
+        a: String,
+
+        /// The newer data file. Defaults to the tupp data file.
+        b: Option<String>,
+    },
+
+    /// Merge another copy of the data file, edited separately, into this one.
+    MergeFile {
+        /// The other copy of the data file.
+        other: String,
+
+        /// The data file both copies started from, so that deletions are
+        /// merged and only fields changed on both sides conflict.
+        #[clap(long)]
+        base: Option<String>,
+
+        /// Resolve conflicts with this copy's values (a) or the other's (b) instead of prompting.
+        #[clap(long, value_enum)]
+        prefer: Option<Side>,
+
+        /// Show what would be merged without saving anything.
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// List the upcoming birthdays of living contacts.
+    Birthdays {
+        /// How many days ahead to look.
+        #[clap(short, long, default_value_t = 30)]
+        days: u32,
+    },
+
+    /// Search email addresses for mail clients, in mutt's `query_command` format.
+    Query {
+        /// The text to search for in names and email addresses.
+        text: String,
+
+        /// Leave out the status line mutt expects first (for aerc).
+        #[clap(long)]
+        no_header: bool,
+    },
+
+    /// Create or update contacts from the From and Cc headers of a message read on stdin.
+    AddFromMail {
+        /// Only read the From header.
+        #[clap(long)]
+        from_only: bool,
+
+        /// Show what would be added without saving anything.
+        #[clap(long)]
+        dry_run: bool,
+    },
+
     /// Start an HTTP API server.
     Serve {
         /// Port to listen on.
@@ -49,15 +169,42 @@ pub enum Commands {
 
 #[derive(Subcommand, Debug)]
 pub enum ContactCommand {
-    /// List all contacts.
+    /// List all contacts, or those matching a query.
     List {
-        /// Display pattern for contact names (e.g., "TITLE FIRST LAST").
+        /// Filter, e.g. 'group:family AND birth.month:6 AND NOT email:*@oldcorp.com'.
+        /// Fields: id, name, title, first, middle, last, post, gender, alive, email,
+        /// phone, social, group, address, city, country, birth[.year|.month|.day], death[...],
+        /// anniversary[...].
+        query: Option<String>,
+
+        /// Name format: a preset (formal, sort, short), a template such as
+        /// "{last:upper}[, {first}]", or a legacy pattern such as "TITLE FIRST LAST".
         #[clap(short, long, default_value = "TITLE FIRST LAST")]
         pattern: String,
 
         /// Show contact IDs in the output.
         #[clap(short = 'i', long)]
         show_ids: bool,
+
+        /// Sort on these keys, in order (e.g. "last,first"). Defaults to insertion order.
+        #[clap(short, long, value_enum, value_delimiter = ',')]
+        sort: Vec<SortKey>,
+
+        /// Reverse the sort order.
+        #[clap(short, long)]
+        reverse: bool,
+
+        /// Show at most this many contacts.
+        #[clap(long)]
+        limit: Option<usize>,
+
+        /// Skip this many contacts first.
+        #[clap(long, default_value_t = 0)]
+        offset: usize,
+
+        /// Print a header before each initial letter.
+        #[clap(long)]
+        by_initial: bool,
     },
 
     /// Register a new contact.
@@ -98,6 +245,50 @@ pub enum ContactCommand {
     Show {
         /// The ID of the contact to display.
         id: String,
+
+        /// Print plain tab-indented lines, without colors or sections.
+        #[clap(long)]
+        plain: bool,
+
+        /// Print the contact in a file format instead, such as vCard.
+        #[clap(short, long, value_enum)]
+        format: Option<ExportFormat>,
+    },
+
+    /// Show a contact as a QR code, to scan it with a phone camera.
+    Qr {
+        /// The ID of the contact to share.
+        id: String,
+
+        /// Only include these fields besides the name.
+        #[clap(long, value_enum, value_delimiter = ',')]
+        only: Vec<QrField>,
+
+        /// How the contact is encoded.
+        #[clap(short, long, value_enum, default_value = "vcard")]
+        kind: QrKind,
+
+        /// Write the code to a PNG image instead.
+        #[clap(long, value_name = "PATH")]
+        png: Option<String>,
+
+        /// Write the code as SVG, to a file or to the standard output with "-".
+        #[clap(long, value_name = "PATH")]
+        svg: Option<String>,
+
+        /// Draw dark modules instead of light ones, for light terminals.
+        #[clap(long)]
+        invert: bool,
+    },
+
+    /// Edit a contact as text in $VISUAL or $EDITOR.
+    Edit {
+        /// The ID of the contact to edit.
+        id: String,
+
+        /// Edit field by field with interactive prompts instead.
+        #[clap(short = 'w', long)]
+        wizard: bool,
     },
 
     /// Add information to an existing contact.
@@ -108,6 +299,26 @@ pub enum ContactCommand {
         #[clap(subcommand)]
         add_type: AddType,
     },
+
+    /// Merge two contacts describing the same person into the first one.
+    Merge {
+        /// The ID of the contact to keep.
+        id_a: String,
+        /// The ID of the contact to merge into it, which is deleted.
+        id_b: String,
+        /// Resolve conflicting fields with this contact's values instead of prompting.
+        #[clap(long, value_enum)]
+        prefer: Option<Side>,
+    },
+
+    /// Remove information from an existing contact.
+    Remove {
+        /// The ID of the contact to modify.
+        id: String,
+        /// The type of information to remove.
+        #[clap(subcommand)]
+        remove_type: RemoveType,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -143,6 +354,40 @@ pub enum GroupCommand {
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub enum RemoveType {
+    /// Remove an email address. Prompts for it if no label is given.
+    Email {
+        #[clap(short = 'l', long)]
+        label: Option<String>,
+    },
+    /// Remove a phone number. Prompts for it if no label is given.
+    Phone {
+        #[clap(short = 'l', long)]
+        label: Option<String>,
+    },
+    /// Remove a social media account. Prompts for it if no selector is given.
+    Social {
+        #[clap(short = 'l', long)]
+        label: Option<String>,
+        #[clap(short = 'n', long)]
+        network: Option<String>,
+        /// The position of the account in the contact's list, starting at 0.
+        #[clap(short = 'i', long)]
+        index: Option<usize>,
+    },
+    /// Remove contact from a group.
+    Group {
+        /// The name or ID of the group.
+        name_or_id: Option<String>,
+    },
+    /// Remove the link to another contact, on both sides.
+    Link {
+        /// The ID of the linked contact.
+        other_id: Option<String>,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum AddType {
     /// Add a social media account.
@@ -197,7 +442,7 @@ pub enum AddType {
         #[clap(short = 'i', long)]
         indicator: Option<u16>,
         #[clap(short = 'n', long)]
-        number: Option<u32>,
+        number: Option<u64>,
     },
     /// Add contact to a group.
     Group {
@@ -0,0 +1,34 @@
+use chrono::Local;
+
+use crate::birthdays;
+use crate::models::TuppData;
+use crate::output::Output;
+use crate::error::TuppError;
+
+pub fn handle_birthdays_command(days: u32, data: &TuppData, output: &Output) -> Result<(), TuppError> {
+    let today = Local::now().date_naive();
+    let birthdays = birthdays::upcoming(&data.contacts, today, days);
+
+    if !output.is_human() {
+        let records = birthdays
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        return output.print_records(&records, &["date", "days", "name", "age"]);
+    }
+
+    if birthdays.is_empty() {
+        println!("No birthdays in the next {} days.", days);
+        return Ok(());
+    }
+    for birthday in birthdays {
+        let when = match birthday.days {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            n => format!("in {} days", n),
+        };
+        let age = birthday.age.map(|age| format!(", turns {}", age)).unwrap_or_default();
+        println!("{}\t{}\t{}{}", birthday.date.format("%a %d %b"), when, birthday.name, age);
+    }
+    Ok(())
+}
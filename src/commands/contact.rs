@@ -1,24 +1,69 @@
-use crate::cli::{ContactCommand, AddType};
+use crate::cli::{ContactCommand, AddType, RemoveType};
 use crate::models::TuppData;
 use crate::contact::Contact;
+use crate::matching::MatchKind;
 use crate::group::Group;
 use crate::interactions;
+use crate::merge;
+use crate::query::Query;
+use crate::sort;
+use crate::output::Output;
+use crate::card;
+use crate::qr;
+use crate::formats::{self, ExportFormat};
+use crate::name_format::{NameFormat, FIRST_LAST};
 use crate::error::TuppError;
 use crate::storage::save_data;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Columns shown by tabular output formats when none are chosen.
+const CONTACT_COLUMNS: &[&str] = &["identifier", "first", "last", "emails", "phones"];
 
 pub fn handle_contact_command(
     command: ContactCommand,
     data: &mut TuppData,
     file_path: &PathBuf,
+    output: &Output,
 ) -> Result<(), TuppError> {
     match command {
-        ContactCommand::List { pattern, show_ids } => {
-            for contact in &data.contacts {
+        ContactCommand::List { query, pattern, show_ids, sort, reverse, limit, offset, by_initial } => {
+            let query = query.as_deref().map(Query::parse).transpose()?;
+            let format = NameFormat::resolve(&pattern)?;
+            let mut contacts: Vec<&Contact> = data.contacts
+                .iter()
+                .filter(|contact| query.as_ref().is_none_or(|q| q.matches(contact, data)))
+                .collect();
+
+            if !sort.is_empty() {
+                sort::sort_contacts(&mut contacts, &sort, reverse);
+            } else if reverse {
+                contacts.reverse();
+            }
+
+            let contacts = contacts.into_iter().skip(offset).take(limit.unwrap_or(usize::MAX));
+            if !output.is_human() {
+                let records = contacts.map(serde_json::to_value).collect::<Result<Vec<_>, _>>()?;
+                return output.print_records(&records, CONTACT_COLUMNS);
+            }
+
+            let mut current_initial = None;
+            for contact in contacts {
+                if by_initial {
+                    let initial = sort::initial(contact, &sort);
+                    if current_initial != Some(initial) {
+                        if current_initial.is_some() {
+                            println!();
+                        }
+                        println!("{}", initial);
+                        current_initial = Some(initial);
+                    }
+                }
                 if show_ids {
-                    println!("{}\t{}", contact.identifier, contact.format_name(&pattern));
+                    println!("{}\t{}", contact.identifier, format.render(&contact.identity));
                 } else {
-                    println!("{}", contact.format_name(&pattern));
+                    println!("{}", format.render(&contact.identity));
                 }
             }
         },
@@ -32,7 +77,7 @@ pub fn handle_contact_command(
             println!("{}", new_id);
         },
         ContactCommand::Del { id } => {
-            let id_uuid = if let Some(contact) = find_best_match(&data.contacts, &id) {
+            let id_uuid = if let Some(contact) = find_best_match(&data.contacts, &id)? {
                 contact.identifier
             } else {
                 println!("No contact found matching '{}'.", id);
@@ -50,29 +95,113 @@ pub fn handle_contact_command(
             }
         },
         ContactCommand::Find { text } => {
-            if let Some(contact) = find_best_match(&data.contacts, &text) {
-                println!("{}", contact.identifier);
-            } else {
+            let results = Contact::search(&data.contacts, &text);
+            if !output.is_human() {
+                let records = results.iter().map(|r| serde_json::to_value(r.contact)).collect::<Result<Vec<_>, _>>()?;
+                return output.print_records(&records, CONTACT_COLUMNS);
+            }
+            if results.is_empty() {
                 println!("No contact found matching '{}'.", text);
             }
+            for result in results {
+                println!(
+                    "{}\t{}\t{} match on {}",
+                    result.contact.identifier,
+                    result.contact.format_name(&FIRST_LAST),
+                    result.kind,
+                    result.field
+                );
+            }
+        },
+        ContactCommand::Show { id, plain, format } => {
+            if let Some(contact) = find_best_match(&data.contacts, &id)? {
+                match format {
+                    Some(ExportFormat::Json) => {
+                        println!("{}", serde_json::to_string_pretty(contact)?);
+                        return Ok(());
+                    },
+                    Some(format) => {
+                        print!("{}", formats::export(format, &[contact], data, &Default::default())?);
+                        return Ok(());
+                    },
+                    None => {},
+                }
+                if !output.is_human() {
+                    return output.print_record(&serde_json::to_value(contact)?, CONTACT_COLUMNS);
+                }
+                if plain {
+                    println!("{}", contact);
+                } else {
+                    println!("{}", card::render_card(contact, data));
+                }
+            } else {
+                println!("No contact found matching '{}'.", id);
+            }
+        },
+        ContactCommand::Qr { id, only, kind, png, svg, invert } => {
+            let Some(contact) = find_best_match(&data.contacts, &id)? else {
+                println!("No contact found matching '{}'.", id);
+                return Ok(());
+            };
+            let code = qr::encode(&qr::payload(contact, kind, &only))?;
+            if let Some(path) = &png {
+                qr::write_png(&code, Path::new(path))?;
+                println!("QR code of {} written to {}.", contact.format_name(&FIRST_LAST), path);
+            }
+            match svg.as_deref() {
+                Some("-") => println!("{}", qr::render_svg(&code)),
+                Some(path) => {
+                    fs::write(path, qr::render_svg(&code))?;
+                    println!("QR code of {} written to {}.", contact.format_name(&FIRST_LAST), path);
+                },
+                None if png.is_none() => print!("{}", qr::render_terminal(&code, invert)),
+                None => {},
+            }
         },
-        ContactCommand::Show { id } => {
-            if let Some(contact) = find_best_match(&data.contacts, &id) {
-                println!("{}", contact);
+        ContactCommand::Edit { id, wizard } => {
+            let index = if let Some(contact) = find_best_match(&data.contacts, &id)? {
+                let identifier = contact.identifier;
+                data.contacts.iter().position(|c| c.identifier == identifier).unwrap()
             } else {
                 println!("No contact found matching '{}'.", id);
+                return Ok(());
+            };
+
+            if wizard {
+                if interactions::edit_contact_wizard(&mut data.contacts[index])? {
+                    data.contacts[index].touch();
+                    save_data(file_path, data)?;
+                    println!("Contact updated.");
+                }
+            } else if let Some(edited) = interactions::edit_contact_in_editor(data, index)? {
+                data.contacts[index] = edited;
+                data.contacts[index].touch();
+                save_data(file_path, data)?;
+                println!("Contact updated.");
             }
         },
         ContactCommand::Add { id, add_type } => {
-            let contact_identifier = if let Some(contact) = find_best_match(&data.contacts, &id) {
+            let contact_identifier = if let Some(contact) = find_best_match(&data.contacts, &id)? {
                 contact.identifier
             } else {
                 println!("No contact found matching '{}'.", id);
                 return Ok(());
             };
             
+            // Resolve the other contact of a link once, as an ambiguous query prompts
+            let other_identifier = if let AddType::Link { other_id, .. } = &add_type {
+                if let Some(other) = find_best_match(&data.contacts, other_id)? {
+                    Some(other.identifier)
+                } else {
+                    println!("No contact found matching '{}'.", other_id);
+                    return Ok(());
+                }
+            } else {
+                None
+            };
+
             // Check for duplicate for the type being added
-            if let Some(contact) = find_best_match(&data.contacts, &id) {
+            if let Some(contact) = data.contacts.iter().find(|c| c.identifier == contact_identifier) {
                 match &add_type {
                     AddType::Email { label, .. } => {
                         let label_str = label.clone().unwrap_or_else(|| "default".to_string());
@@ -108,19 +237,17 @@ pub fn handle_contact_command(
                     },
                     AddType::Group { name_or_id } => {
                         if let Some(groups) = &contact.groups {
-                            if let Some(group) = find_group_best_match(&data.groups, &name_or_id) {
+                            if let Some(group) = find_group_best_match(&data.groups, name_or_id) {
                                         if groups.contains(&group.identifier) {
                                             return Err(TuppError::Duplicate(format!("Contact already in group '{}'", group.name)));
                                         }
                             }
                         }
                     },
-                    AddType::Link { other_id, .. } => {
-                        if let Some(other) = find_best_match(&data.contacts, &other_id) {
-                            if let Some(links) = &contact.links {
-                                if links.iter().any(|l| l.target == other.identifier) {
-                                    return Err(TuppError::Duplicate("Link to this contact already exists".to_string()));
-                                }
+                    AddType::Link { .. } => {
+                        if let (Some(other_identifier), Some(links)) = (other_identifier, &contact.links) {
+                            if links.iter().any(|l| l.target == other_identifier) {
+                                return Err(TuppError::Duplicate("Link to this contact already exists".to_string()));
                             }
                         }
                     },
@@ -128,36 +255,11 @@ pub fn handle_contact_command(
                 }
             }
             // Handle link case separately to avoid borrowing issues
-            if let AddType::Link { other_id, relation_type } = &add_type {
-                let other_identifier = if let Some(contact) = find_best_match(&data.contacts, other_id) {
-                    contact.identifier
-                } else {
-                    println!("No contact found matching '{}'.", other_id);
-                    return Ok(());
-                };
-                
+            if let AddType::Link { relation_type, .. } = &add_type {
+                let other_identifier = other_identifier.unwrap();
+
                 // Find both contacts and create bidirectional link
-                let mut contact_a_index = None;
-                let mut contact_b_index = None;
-                
-                for (index, contact) in data.contacts.iter().enumerate() {
-                    if contact.identifier == contact_identifier {
-                        contact_a_index = Some(index);
-                    }
-                    if contact.identifier == other_identifier {
-                        contact_b_index = Some(index);
-                    }
-                }
-                
-                if let (Some(a_idx), Some(b_idx)) = (contact_a_index, contact_b_index) {
-                    let (contact_a, contact_b) = if a_idx < b_idx {
-                        let (left, right) = data.contacts.split_at_mut(b_idx);
-                         (&mut left[a_idx], &mut right[0])
-                    } else {
-                        let (left, right) = data.contacts.split_at_mut(a_idx);
-                        (&mut right[0], &mut left[b_idx])
-                    };
-                    
+                if let Some((contact_a, contact_b)) = contact_pair_mut(&mut data.contacts, contact_identifier, other_identifier) {
                     if let Err(error) = Contact::create_bidirectional_link(contact_a, contact_b, relation_type.clone()) {
                         println!("{}", error);
                         return Ok(());
@@ -210,16 +312,148 @@ pub fn handle_contact_command(
                     }
                 }
             }
-            
+
+            touch(&mut data.contacts, &[Some(contact_identifier), other_identifier]);
             save_data(file_path, data)?;
             println!("Information added successfully!");
         },
+        ContactCommand::Merge { id_a, id_b, prefer } => {
+            let mut identifiers = Vec::new();
+            for id in [&id_a, &id_b] {
+                if let Some(contact) = find_best_match(&data.contacts, id)? {
+                    identifiers.push(contact.identifier);
+                } else {
+                    println!("No contact found matching '{}'.", id);
+                    return Ok(());
+                }
+            }
+
+            let mut resolve = |field: &str, a: &str, b: &str| match prefer {
+                Some(side) => side,
+                None => interactions::resolve_conflict_interactive(field, a, b),
+            };
+            merge::merge_contacts(data, identifiers[0], identifiers[1], &mut resolve)?;
+            touch(&mut data.contacts, &[Some(identifiers[0])]);
+
+            save_data(file_path, data)?;
+            println!("{}", identifiers[0]);
+        },
+        ContactCommand::Remove { id, remove_type } => {
+            let contact_identifier = if let Some(contact) = find_best_match(&data.contacts, &id)? {
+                contact.identifier
+            } else {
+                println!("No contact found matching '{}'.", id);
+                return Ok(());
+            };
+
+            let mut other_identifier = None;
+            if let RemoveType::Link { other_id } = remove_type {
+                let target_identifier = if let Some(other_id) = other_id {
+                    if let Some(other) = find_best_match(&data.contacts, &other_id)? {
+                        other.identifier
+                    } else {
+                        println!("No contact found matching '{}'.", other_id);
+                        return Ok(());
+                    }
+                } else {
+                    let contact = data.contacts.iter().find(|c| c.identifier == contact_identifier).unwrap();
+                    if let Some(target) = interactions::select_link_target(contact, &data.contacts) {
+                        target
+                    } else {
+                        println!("Error: This contact has no relationship.");
+                        return Ok(());
+                    }
+                };
+
+                let removed = match contact_pair_mut(&mut data.contacts, contact_identifier, target_identifier) {
+                    Some((contact_a, contact_b)) => Contact::remove_bidirectional_link(contact_a, contact_b),
+                    None => {
+                        // Dangling link to a contact that no longer exists
+                        let contact = data.contacts.iter_mut().find(|c| c.identifier == contact_identifier).unwrap();
+                        let links = contact.links.get_or_insert_with(Vec::new);
+                        let initial_len = links.len();
+                        links.retain(|link| link.target != target_identifier);
+                        let removed = links.len() < initial_len;
+                        if links.is_empty() {
+                            contact.links = None;
+                        }
+                        removed
+                    }
+                };
+                if !removed {
+                    println!("Error: These contacts are not linked.");
+                    return Ok(());
+                }
+                other_identifier = Some(target_identifier);
+            } else if let Some(contact) = data.contacts.iter_mut().find(|c| c.identifier == contact_identifier) {
+                let success = match remove_type {
+                    RemoveType::Email { label } => interactions::remove_email_from_contact(contact, label),
+                    RemoveType::Phone { label } => interactions::remove_phone_from_contact(contact, label),
+                    RemoveType::Social { label, network, index } => interactions::remove_social_from_contact(contact, label, network, index),
+                    RemoveType::Group { name_or_id } => interactions::remove_group_from_contact(contact, &data.groups, name_or_id),
+                    RemoveType::Link { .. } => unreachable!(), // Already handled above
+                };
+                if !success {
+                    return Ok(());
+                }
+            }
+
+            touch(&mut data.contacts, &[Some(contact_identifier), other_identifier]);
+            save_data(file_path, data)?;
+            println!("Information removed successfully!");
+        },
     }
     Ok(())
 }
 
-fn find_best_match<'a>(contacts: &'a [Contact], text: &str) -> Option<&'a Contact> {
-    Contact::find_best_match(contacts, text)
+/// Mark the given contacts as just modified.
+fn touch(contacts: &mut [Contact], identifiers: &[Option<uuid::Uuid>]) {
+    for contact in contacts.iter_mut() {
+        if identifiers.contains(&Some(contact.identifier)) {
+            contact.touch();
+        }
+    }
+}
+
+/// Borrow two distinct contacts mutably at once, by identifier.
+fn contact_pair_mut(contacts: &mut [Contact], a: uuid::Uuid, b: uuid::Uuid) -> Option<(&mut Contact, &mut Contact)> {
+    let a_idx = contacts.iter().position(|c| c.identifier == a)?;
+    let b_idx = contacts.iter().position(|c| c.identifier == b)?;
+
+    if a_idx < b_idx {
+        let (left, right) = contacts.split_at_mut(b_idx);
+        Some((&mut left[a_idx], &mut right[0]))
+    } else if a_idx > b_idx {
+        let (left, right) = contacts.split_at_mut(a_idx);
+        Some((&mut right[0], &mut left[b_idx]))
+    } else {
+        None
+    }
+}
+
+/// Resolve a query to a single contact. Unless the query names one contact
+/// exactly, prompt for the right one on a terminal, and refuse otherwise, so
+/// that a typo never picks a contact for a command that changes it.
+fn find_best_match<'a>(contacts: &'a [Contact], text: &str) -> Result<Option<&'a Contact>, TuppError> {
+    if let Some(contact) = Contact::find_best_match(contacts, text) {
+        return Ok(Some(contact));
+    }
+    let results = Contact::search(contacts, text);
+    if results.is_empty() {
+        return Ok(None);
+    }
+
+    if std::io::stdin().is_terminal() {
+        return Ok(Some(interactions::select_contact(&results)?));
+    }
+
+    let candidates: Vec<String> = results
+        .iter()
+        .take_while(|r| r.score() == results[0].score())
+        .map(|r| format!("{} ({})", r.contact.format_name(&FIRST_LAST), r.contact.identifier))
+        .collect();
+    let matches = if results[0].kind == MatchKind::Exact { "matches" } else { "only partly matches" };
+    Err(TuppError::Ambiguous(format!("'{}' {} {}", text, matches, candidates.join(", "))))
 }
 
 fn find_group_best_match<'a>(groups: &'a [Group], text: &str) -> Option<&'a Group> {
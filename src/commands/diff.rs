@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use crate::diff::{self, EntryChange};
+use crate::interactions;
+use crate::merge::{self, Side};
+use crate::models::TuppData;
+use crate::storage::{load_data, save_data};
+use crate::error::TuppError;
+
+/// Print the entries that differ from `a` to `b`, with the changed fields of modified ones.
+pub fn handle_diff_command(a: &str, b: Option<&str>, data: &TuppData) -> Result<(), TuppError> {
+    let old = load_data(&PathBuf::from(a))?;
+    let loaded;
+    let new = match b {
+        Some(b) => {
+            loaded = load_data(&PathBuf::from(b))?;
+            &loaded
+        },
+        None => data,
+    };
+
+    let differences = diff::diff_data(&old, new)?;
+    if differences.is_empty() {
+        println!("No differences.");
+        return Ok(());
+    }
+    for (entry, change) in &differences {
+        match change {
+            EntryChange::Added => println!("+ {}", entry),
+            EntryChange::Removed => println!("- {}", entry),
+            EntryChange::Modified(changes) => {
+                println!("~ {}", entry);
+                for change in changes {
+                    println!("\t{}", change);
+                }
+            },
+        }
+    }
+    println!("{} entries differ.", differences.len());
+    Ok(())
+}
+
+/// Three-way merge of `other` into the data, against `base` if given.
+pub fn handle_merge_file_command(
+    other: &str,
+    base: Option<&str>,
+    prefer: Option<Side>,
+    dry_run: bool,
+    data: &mut TuppData,
+    file_path: &PathBuf,
+) -> Result<(), TuppError> {
+    let other = load_data(&PathBuf::from(other))?;
+    let base = base.map(|base| load_data(&PathBuf::from(base))).transpose()?;
+    if base.is_none() {
+        println!("No --base given: deletions are not merged, and every difference is a conflict.");
+    }
+
+    let mut resolve = |field: &str, a: &str, b: &str| match prefer {
+        Some(side) => side,
+        None => interactions::resolve_conflict_interactive(field, a, b),
+    };
+    let (merged, conflicts) = merge::merge_data(base.as_ref(), data, &other, &mut resolve)?;
+
+    let changes = diff::diff_data(data, &merged)?;
+    for (entry, change) in &changes {
+        match change {
+            EntryChange::Added => println!("Added {}", entry),
+            EntryChange::Removed => println!("Deleted {}", entry),
+            EntryChange::Modified(_) => println!("Updated {}", entry),
+        }
+    }
+    for conflict in &conflicts {
+        let kept = match conflict.kept {
+            Side::A => "this copy",
+            Side::B => "the other copy",
+        };
+        println!("Conflict on {}: kept the value of {}", conflict.field, kept);
+    }
+    println!("{} entries changed, {} conflicts.", changes.len(), conflicts.len());
+
+    if dry_run {
+        println!("Dry run, nothing was saved.");
+    } else {
+        *data = merged;
+        save_data(file_path, data)?;
+    }
+    Ok(())
+}
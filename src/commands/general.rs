@@ -2,7 +2,10 @@ use crate::cli::Commands;
 use crate::models::TuppData;
 use crate::storage::save_data;
 use crate::error::TuppError;
-use std::path::PathBuf;
+use crate::contact::Contact;
+use crate::formats::{self, ExportFormat, ExportOptions};
+use crate::formats::csv::Mapping;
+use std::path::{Path, PathBuf};
 use std::fs;
 
 pub fn handle_general_command(
@@ -11,8 +14,21 @@ pub fn handle_general_command(
     file_path: &PathBuf,
 ) -> Result<(), TuppError> {
     match command {
-        Commands::Export { path } => { 
-            save_data(&PathBuf::from(path), data)?
+        Commands::Export { path, format, mapping, base_dn } => match format {
+            ExportFormat::Json => save_data(&PathBuf::from(path), data)?,
+            format => {
+                let contacts: Vec<&Contact> = data.contacts.iter().collect();
+                if let Some(flavor) = format.site_flavor() {
+                    formats::site::write(Path::new(&path), flavor, &contacts, data)?;
+                } else {
+                    let options = ExportOptions {
+                        mapping: mapping.as_deref().map(Mapping::load).transpose()?,
+                        base_dn,
+                    };
+                    let content = formats::export(format, &contacts, data, &options)?;
+                    fs::write(&path, content).map_err(TuppError::Io)?;
+                }
+            },
         },
         Commands::Init => {
             fs::write(file_path, "{\"contacts\": [], \"groups\": []}")
@@ -27,6 +43,19 @@ pub fn handle_general_command(
             crate::validation::validate_file(&p)?;
             println!("Validation successful.");
         },
+        Commands::Dedupe { min_score, json } => {
+            let duplicates = crate::dedupe::find_duplicates(&data.contacts, min_score);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&duplicates)?);
+            } else if duplicates.is_empty() {
+                println!("No likely duplicates found.");
+            } else {
+                for d in duplicates {
+                    println!("{:.2}\t{} ({})\t{} ({})", d.score, d.a_name, d.a, d.b_name, d.b);
+                    println!("\t{}", d.reasons.join(", "));
+                }
+            }
+        },
         _ => {}
     }
     Ok(())
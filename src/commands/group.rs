@@ -3,15 +3,28 @@ use crate::models::TuppData;
 use crate::group::Group;
 use crate::error::TuppError;
 use crate::storage::save_data;
+use crate::output::Output;
+use crate::name_format::FIRST_LAST;
+use serde_json::Value;
 use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Columns shown by tabular output formats when none are chosen.
+const GROUP_COLUMNS: &[&str] = &["identifier", "name", "parent", "members"];
 
 pub fn handle_group_command(
     command: GroupCommand,
     data: &mut TuppData,
     file_path: &PathBuf,
+    output: &Output,
 ) -> Result<(), TuppError> {
     match command {
         GroupCommand::List { show_ids } => {
+            if !output.is_human() {
+                let mut records = Vec::new();
+                collect_records(&data.groups, data, &mut records);
+                return output.print_records(&records, GROUP_COLUMNS);
+            }
             if data.groups.is_empty() {
                 println!("No groups found.");
             } else {
@@ -65,6 +78,13 @@ pub fn handle_group_command(
             }
         }
         GroupCommand::Find { text } => {
+            if !output.is_human() {
+                let records: Vec<Value> = find_group_best_match(&data.groups, &text)
+                    .map(|group| group_record(group, data))
+                    .into_iter()
+                    .collect();
+                return output.print_records(&records, GROUP_COLUMNS);
+            }
             if let Some(group) = find_group_best_match(&data.groups, &text) {
                 println!("{} ({})", group.name, group.identifier);
             } else {
@@ -73,11 +93,14 @@ pub fn handle_group_command(
         }
         GroupCommand::Show { id } => {
             if let Some(group) = find_group_best_match(&data.groups, &id) {
+                if !output.is_human() {
+                    return output.print_record(&group_record(group, data), GROUP_COLUMNS);
+                }
                 println!("Group: {}", group.name);
                 println!("ID: {}", group.identifier);
                 
                 let members: Vec<_> = data.contacts.iter()
-                    .filter(|c| c.groups.as_ref().map_or(false, |g| g.contains(&group.identifier)))
+                    .filter(|c| c.groups.as_ref().is_some_and(|g| g.contains(&group.identifier)))
                     .collect();
                 
                 if members.is_empty() {
@@ -85,7 +108,7 @@ pub fn handle_group_command(
                 } else {
                     println!("Members:");
                     for m in members {
-                        println!("  - {} ({})", m.format_name("FIRST LAST"), m.identifier);
+                        println!("  - {} ({})", m.format_name(&FIRST_LAST), m.identifier);
                     }
                 }
             } else {
@@ -96,6 +119,41 @@ pub fn handle_group_command(
     Ok(())
 }
 
+/// A group as a flat record: its subgroups and members are given by identifier.
+fn group_record(group: &Group, data: &TuppData) -> Value {
+    let members: Vec<Uuid> = data.contacts.iter()
+        .filter(|c| c.groups.as_ref().is_some_and(|g| g.contains(&group.identifier)))
+        .map(|c| c.identifier)
+        .collect();
+
+    serde_json::json!({
+        "identifier": group.identifier,
+        "name": group.name,
+        "parent": parent_of(&data.groups, &group.identifier),
+        "subgroups": group.subgroups.iter().map(|g| g.identifier).collect::<Vec<_>>(),
+        "members": members,
+    })
+}
+
+fn collect_records(groups: &[Group], data: &TuppData, records: &mut Vec<Value>) {
+    for group in groups {
+        records.push(group_record(group, data));
+        collect_records(&group.subgroups, data, records);
+    }
+}
+
+fn parent_of(groups: &[Group], id: &Uuid) -> Option<Uuid> {
+    for group in groups {
+        if group.subgroups.iter().any(|g| &g.identifier == id) {
+            return Some(group.identifier);
+        }
+        if let Some(parent) = parent_of(&group.subgroups, id) {
+            return Some(parent);
+        }
+    }
+    None
+}
+
 fn find_group_best_match<'a>(groups: &'a [Group], text: &str) -> Option<&'a Group> {
     Group::find_best_match(groups, text)
 }
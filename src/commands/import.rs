@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use crate::formats::ImportFormat;
+use crate::formats::csv::Mapping;
+use crate::import::{self, OnDuplicate, Outcome};
+use crate::models::TuppData;
+use crate::storage::save_data;
+use crate::error::TuppError;
+
+// One argument per option of the import command
+#[allow(clippy::too_many_arguments)]
+pub fn handle_import_command(
+    path: &str,
+    format: Option<ImportFormat>,
+    mapping: Option<&str>,
+    dry_run: bool,
+    on_duplicate: OnDuplicate,
+    min_score: f64,
+    data: &mut TuppData,
+    file_path: &PathBuf,
+) -> Result<(), TuppError> {
+    let default_country_code = crate::config::config().default_country_code;
+    let format = format.map_or_else(|| ImportFormat::from_path(path), Ok)?;
+    let mapping = mapping.map(Mapping::load).transpose()?;
+    let importer = format.importer(mapping);
+
+    let fragment = importer.read(Path::new(path), default_country_code)?;
+    fragment.report.print();
+    let unmapped: Vec<Vec<String>> = fragment.contacts.iter().map(|i| i.unmapped.clone()).collect();
+
+    let outcomes = import::import_contacts(data, fragment.contacts, importer.name(), on_duplicate, min_score)?;
+
+    let (mut added, mut merged, mut updated, mut unchanged, mut skipped) = (0, 0, 0, 0, 0);
+    for ((name, outcome), unmapped) in outcomes.iter().zip(unmapped) {
+        match outcome {
+            Outcome::Added(id) => {
+                added += 1;
+                println!("Added {} ({})", name, id);
+            },
+            Outcome::Merged { into } => {
+                merged += 1;
+                println!("Merged {} into {}", name, into);
+            },
+            Outcome::Updated { into } => {
+                updated += 1;
+                println!("Updated {} ({})", name, into);
+            },
+            Outcome::Unchanged => unchanged += 1,
+            Outcome::Skipped { duplicate_of, reasons } => {
+                skipped += 1;
+                println!("Skipped {}, likely a duplicate of {} ({})", name, duplicate_of, reasons.join(", "));
+            },
+        }
+        if !unmapped.is_empty() && matches!(outcome, Outcome::Added(_) | Outcome::Merged { .. } | Outcome::Updated { .. }) {
+            println!("\tNot imported: {}", unmapped.join(", "));
+        }
+    }
+    println!("{} added, {} merged, {} updated, {} unchanged, {} skipped.", added, merged, updated, unchanged, skipped);
+
+    if dry_run {
+        println!("Dry run, nothing was saved.");
+    } else {
+        save_data(file_path, data)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::contact::Contact;
+use crate::mail::{self, Mailbox};
+use crate::matching::MatchKind;
+use crate::merge::unique_label;
+use crate::models::{Email, Identity, TuppData};
+use crate::sanitize::fold;
+use crate::storage::save_data;
+use crate::name_format::FIRST_LAST;
+use crate::error::TuppError;
+
+/// Print the emails of the contacts matching `text` as `email<TAB>name<TAB>info`
+/// lines, after a status line, as mutt's `query_command` expects.
+pub fn handle_query_command(text: &str, no_header: bool, data: &TuppData) -> Result<(), TuppError> {
+    let results = Contact::search(&data.contacts, text);
+    // Completion wants what was typed, so typos only count when nothing else matches
+    let precise: Vec<_> = results.iter().filter(|r| r.kind > MatchKind::Fuzzy).collect();
+    let results = if precise.is_empty() { results.iter().collect() } else { precise };
+
+    let mut lines = Vec::new();
+    for result in results {
+        let contact = result.contact;
+        for email in contact.emails.iter().flatten() {
+            let Some(address) = &email.address else { continue };
+            let info = email.label.clone().filter(|l| l != "default").unwrap_or_default();
+            lines.push(format!("{}\t{}\t{}", address, contact.format_name(&FIRST_LAST), info));
+        }
+    }
+
+    if !no_header {
+        println!("tupp: {} addresses found for '{}'", lines.len(), text);
+    }
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Read a message on stdin and add the senders and copied recipients as
+/// contacts. An address that is already known only fills the missing names
+/// of its contact; a name that is already known gets the address.
+pub fn handle_add_from_mail_command(from_only: bool, dry_run: bool, data: &mut TuppData, file_path: &PathBuf) -> Result<(), TuppError> {
+    let mut bytes = Vec::new();
+    std::io::stdin().read_to_end(&mut bytes).map_err(TuppError::Io)?;
+    let message = String::from_utf8_lossy(&bytes);
+
+    let headers: &[&str] = if from_only { &["From"] } else { &["From", "Cc"] };
+    let mailboxes = mail::mailboxes(&message, headers);
+    if mailboxes.is_empty() {
+        return Err(TuppError::Validation(format!("No address found in the {} headers of the message", headers.join(" and "))));
+    }
+
+    for mailbox in mailboxes {
+        let (first_name, last_name) = split_name(&mailbox);
+
+        let known = data.contacts.iter_mut().find(|c| {
+            c.emails.iter().flatten().any(|e| e.address.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(&mailbox.address)))
+        });
+        if let Some(contact) = known {
+            let identity = &mut contact.identity;
+            let missing = identity.first_name.is_none() && identity.last_name.is_none();
+            if missing && (first_name.is_some() || last_name.is_some()) {
+                identity.first_name = first_name;
+                identity.last_name = last_name;
+                contact.touch();
+                println!("Updated {} <{}> ({})", contact.format_name(&FIRST_LAST), mailbox.address, contact.identifier);
+            } else {
+                println!("Already known: {} <{}> ({})", contact.format_name(&FIRST_LAST), mailbox.address, contact.identifier);
+            }
+            continue;
+        }
+
+        let email = |emails: &[Email]| Email {
+            label: unique_label(Some("default".to_string()), emails.iter().map(|e| e.label.as_deref())),
+            address: Some(mailbox.address.clone()),
+        };
+
+        let name = fold(&[first_name.as_deref(), last_name.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" "));
+        let mut namesakes = data.contacts.iter_mut().filter(|c| mailbox.name.is_some() && fold(&c.format_name(&FIRST_LAST)) == name);
+        if let (Some(contact), None) = (namesakes.next(), namesakes.next()) {
+            let emails = contact.emails.get_or_insert_with(Vec::new);
+            let new_email = email(emails);
+            emails.push(new_email);
+            contact.touch();
+            println!("Added <{}> to {} ({})", mailbox.address, contact.format_name(&FIRST_LAST), contact.identifier);
+            continue;
+        }
+
+        let mut contact = Contact::new(Identity { first_name, last_name, ..Default::default() });
+        contact.emails = Some(vec![email(&[])]);
+        println!("Added {} <{}> ({})", contact.format_name(&FIRST_LAST), mailbox.address, contact.identifier);
+        data.contacts.push(contact);
+    }
+
+    if dry_run {
+        println!("Dry run, nothing was saved.");
+    } else {
+        save_data(file_path, data)?;
+    }
+    Ok(())
+}
+
+/// First and last names from a display name, "Jean Dupont" or "Dupont, Jean",
+/// or else from the local part of the address, as in "jean.dupont@".
+fn split_name(mailbox: &Mailbox) -> (Option<String>, Option<String>) {
+    let name = match &mailbox.name {
+        Some(name) => name.clone(),
+        None => mailbox.address
+            .split('@')
+            .next()
+            .unwrap_or_default()
+            .split(['.', '_', '-'])
+            .filter(|w| !w.is_empty() && !w.chars().all(|c| c.is_ascii_digit()))
+            .map(capitalize)
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+
+    let (first, last) = match name.split_once(',') {
+        Some((last, first)) => (first.trim().to_string(), last.trim().to_string()),
+        None => match name.trim().split_once(' ') {
+            Some((first, last)) => (first.to_string(), last.trim().to_string()),
+            None => (name.trim().to_string(), String::new()),
+        },
+    };
+    (Some(first).filter(|f| !f.is_empty()), Some(last).filter(|l| !l.is_empty()))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
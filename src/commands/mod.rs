@@ -2,8 +2,16 @@ pub mod contact;
 pub mod group;
 pub mod general;
 pub mod serve;
+pub mod import;
+pub mod birthdays;
+pub mod mail;
+pub mod diff;
 
 pub use contact::handle_contact_command;
 pub use group::handle_group_command;
 pub use general::handle_general_command;
 pub use serve::handle_serve_command;
+pub use import::handle_import_command;
+pub use birthdays::handle_birthdays_command;
+pub use mail::{handle_add_from_mail_command, handle_query_command};
+pub use diff::{handle_diff_command, handle_merge_file_command};
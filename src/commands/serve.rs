@@ -1,33 +1,200 @@
 use std::env;
 use std::path::PathBuf;
 
-use tiny_http::{Header, Method, Response, Server};
+use serde_json::Value;
+use tiny_http::{Header, Method, Request, Response, Server};
 use uuid::Uuid;
 
 use crate::contact::{Contact, Link};
+use crate::diff;
 use crate::error::TuppError;
+use crate::models::TuppData;
+use crate::query::Query;
+use crate::formats::{self, ExportFormat};
 use crate::storage::{load_data, save_data};
 
 const TOKEN_ENV: &str = "TUPP_API_TOKEN";
+/// A read-only token that only gives access to the calendar, as it appears
+/// in the subscription URL.
+const CALENDAR_TOKEN_ENV: &str = "TUPP_CALENDAR_TOKEN";
+
+type Resp = Response<std::io::Cursor<Vec<u8>>>;
 
 fn cors_headers() -> Vec<Header> {
     vec![
         Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap(),
-        Header::from_bytes("Access-Control-Allow-Methods", "GET, POST, OPTIONS").unwrap(),
+        Header::from_bytes("Access-Control-Allow-Methods", "GET, POST, PUT, PATCH, DELETE, OPTIONS").unwrap(),
         Header::from_bytes("Access-Control-Allow-Headers", "Authorization, Content-Type").unwrap(),
+        Header::from_bytes("Access-Control-Expose-Headers", "Location").unwrap(),
     ]
 }
 
-fn json_resp(body: String, status: u16) -> Response<std::io::Cursor<Vec<u8>>> {
+fn json_resp(body: String, status: u16) -> Resp {
+    typed_resp(body, status, "application/json")
+}
+
+fn typed_resp(body: String, status: u16, content_type: &str) -> Resp {
     let mut resp = Response::from_string(body)
         .with_status_code(status)
-        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        .with_header(Header::from_bytes("Content-Type", content_type).unwrap());
     for h in cors_headers() {
         resp.add_header(h);
     }
     resp
 }
 
+fn empty_resp(status: u16) -> Resp {
+    let mut resp = Response::from_data(Vec::new()).with_status_code(status);
+    for h in cors_headers() {
+        resp.add_header(h);
+    }
+    resp
+}
+
+/// Every error has the same body: `{"error": <message>, "status": <code>}`.
+fn error_resp(status: u16, message: impl std::fmt::Display) -> Resp {
+    json_resp(serde_json::json!({"error": message.to_string(), "status": status}).to_string(), status)
+}
+
+/// An error of tupp itself: invalid data is the client's fault, the rest is the server's.
+fn tupp_error_resp(error: TuppError) -> Resp {
+    match error {
+        TuppError::Validation(_) => error_resp(400, error),
+        _ => error_resp(500, error),
+    }
+}
+
+/// A contact as JSON, with a `Location` header when it was just created.
+fn contact_resp(contact: &Contact, status: u16) -> Resp {
+    let resp = match serde_json::to_string(contact) {
+        Ok(json) => json_resp(json, status),
+        Err(e) => return error_resp(500, e),
+    };
+    if status == 201 {
+        resp.with_header(location(contact.identifier))
+    } else {
+        resp
+    }
+}
+
+fn location(id: Uuid) -> Header {
+    Header::from_bytes("Location", format!("/contacts/{}", id)).unwrap()
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request.headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+/// Read the body of a request as a JSON object: contacts and patches of
+/// contacts are objects, and routes index into them.
+fn read_json(request: &mut Request) -> Result<Value, Resp> {
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body).map_err(|e| error_resp(400, e))?;
+    let value: Value = serde_json::from_str(&body).map_err(|e| error_resp(400, format!("Invalid JSON: {}", e)))?;
+    if !value.is_object() {
+        return Err(error_resp(400, "Expected a JSON object"));
+    }
+    Ok(value)
+}
+
+/// Add the reciprocal of each link of a contact to its targets, unless they
+/// already link back to it.
+fn mirror_links(data: &mut TuppData, contact_id: Uuid) {
+    let links_to_mirror: Vec<(Uuid, _)> = data.contacts
+        .iter()
+        .find(|c| c.identifier == contact_id)
+        .and_then(|c| c.links.as_ref())
+        .map(|ls| ls.iter().map(|l| (l.target, Contact::get_reciprocal_relation(&l.relation))).collect())
+        .unwrap_or_default();
+    for (target_id, reciprocal) in links_to_mirror {
+        if target_id == contact_id { continue; }
+        if let Some(target) = data.contacts.iter_mut().find(|c| c.identifier == target_id) {
+            let already_exists = target.links.as_ref()
+                .map(|ls| ls.iter().any(|l| l.target == contact_id))
+                .unwrap_or(false);
+            if !already_exists {
+                let new_link = Link { target: contact_id, relation: reciprocal };
+                target.links.get_or_insert_with(Vec::new).push(new_link);
+            }
+        }
+    }
+}
+
+/// Pick the format of a contact list from the Accept header: jCard, xCard or
+/// vCard when one of them has the highest `q` value, or else tupp JSON
+/// (`None`), which `application/json` and `*/*` ask for. Types with `q=0` are
+/// refused, unknown types are ignored, and ties go to the first listed.
+pub fn negotiate(accept: Option<&str>) -> Option<(ExportFormat, &'static str)> {
+    let formats = [
+        ("application/vcard+json", ExportFormat::Jcard),
+        ("application/vcard+xml", ExportFormat::Xcard),
+        ("text/vcard", ExportFormat::Vcard),
+    ];
+
+    let mut best: Option<(f32, Option<(ExportFormat, &'static str)>)> = None;
+    for range in accept.unwrap_or_default().split(',') {
+        let mut parts = range.split(';');
+        let media = parts.next().unwrap_or_default().trim();
+        let weight = parts
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+            .map_or(Ok(1.0), |(_, q)| q.trim().parse::<f32>());
+        let Ok(weight) = weight else { continue };
+        if weight <= 0.0 {
+            continue;
+        }
+
+        let choice = if ["application/json", "application/*", "*/*"].iter().any(|m| m.eq_ignore_ascii_case(media)) {
+            None
+        } else if let Some((name, format)) = formats.iter().find(|(m, _)| m.eq_ignore_ascii_case(media)) {
+            Some((*format, *name))
+        } else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(best_weight, _)| weight > *best_weight) {
+            best = Some((weight, choice));
+        }
+    }
+    best.and_then(|(_, choice)| choice)
+}
+
+/// Read a parameter from the query string of a URL, percent-decoded.
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let query = url.split_once('?')?.1;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    },
+                    None => decoded.push(b'%'),
+                }
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 pub fn handle_serve_command(port: u16, file_path: &PathBuf) -> Result<(), TuppError> {
     let token = env::var(TOKEN_ENV).map_err(|_| {
         TuppError::Other(format!(
@@ -35,6 +202,7 @@ pub fn handle_serve_command(port: u16, file_path: &PathBuf) -> Result<(), TuppEr
             TOKEN_ENV
         ))
     })?;
+    let calendar_token = env::var(CALENDAR_TOKEN_ENV).ok().filter(|t| !t.is_empty());
 
     let addr = format!("0.0.0.0:{}", port);
     let server = Server::http(&addr)
@@ -43,13 +211,22 @@ pub fn handle_serve_command(port: u16, file_path: &PathBuf) -> Result<(), TuppEr
     eprintln!("tupp listening on http://{}", addr);
 
     for mut request in server.incoming_requests() {
+        let path = request.url().split('?').next().unwrap_or("").trim_end_matches('/').to_string();
+
         // --- Bearer auth ---
+        // Calendar apps subscribe with a plain URL, so GET /calendar.ics also
+        // takes ?token= with the calendar token, never with the API token
         let authorized = request
             .headers()
             .iter()
             .find(|h| h.field.equiv("Authorization"))
             .map(|h| h.value.as_str() == format!("Bearer {}", token))
-            .unwrap_or(false);
+            .unwrap_or(false)
+            || (request.method() == &Method::Get
+                && path == "/calendar.ics"
+                && calendar_token.as_ref().is_some_and(|calendar| {
+                    query_param(request.url(), "token").is_some_and(|t| t == *calendar)
+                }));
 
         // Preflight CORS — no auth required
         if request.method() == &Method::Options {
@@ -62,10 +239,7 @@ pub fn handle_serve_command(port: u16, file_path: &PathBuf) -> Result<(), TuppEr
         }
 
         if !authorized {
-            let _ = request.respond(json_resp(
-                serde_json::json!({"error": "Unauthorized"}).to_string(),
-                401,
-            ));
+            let _ = request.respond(error_resp(401, "Unauthorized"));
             continue;
         }
 
@@ -74,32 +248,68 @@ pub fn handle_serve_command(port: u16, file_path: &PathBuf) -> Result<(), TuppEr
         enum Route {
             GetContacts,
             PostContacts,
+            GetContact(Uuid),
+            PutContact(Uuid),
+            PatchContact(Uuid),
+            DeleteContact(Uuid),
+            GetCalendar,
+            InvalidId,
+            MethodNotAllowed(&'static str),
             NotFound,
         }
 
-        let route = match (
-            request.method(),
-            request.url().split('?').next().unwrap_or("").trim_end_matches('/'),
-        ) {
+        let route = match (request.method(), path.as_str()) {
             (Method::Get, "/contacts") => Route::GetContacts,
             (Method::Post, "/contacts") => Route::PostContacts,
-            _ => Route::NotFound,
+            (_, "/contacts") => Route::MethodNotAllowed("GET, POST, OPTIONS"),
+            (Method::Get, "/calendar.ics") => Route::GetCalendar,
+            (_, "/calendar.ics") => Route::MethodNotAllowed("GET, OPTIONS"),
+            (method, path) => match path.strip_prefix("/contacts/").filter(|id| !id.contains('/')) {
+                Some(id) => match (method, Uuid::parse_str(id)) {
+                    (_, Err(_)) => Route::InvalidId,
+                    (Method::Get, Ok(id)) => Route::GetContact(id),
+                    (Method::Put, Ok(id)) => Route::PutContact(id),
+                    (Method::Patch, Ok(id)) => Route::PatchContact(id),
+                    (Method::Delete, Ok(id)) => Route::DeleteContact(id),
+                    _ => Route::MethodNotAllowed("GET, PUT, PATCH, DELETE, OPTIONS"),
+                },
+                None => Route::NotFound,
+            },
         };
 
         match route {
-            // GET /contacts → return full data as JSON
+            // GET /contacts → return full data as JSON, filtered by the optional ?q= query,
+            // or contacts as jCard, xCard or vCard depending on the Accept header
             Route::GetContacts => {
+                let negotiated = negotiate(header(&request, "Accept").as_deref());
+                let query = match query_param(request.url(), "q").filter(|q| !q.trim().is_empty()).map(|q| Query::parse(&q)).transpose() {
+                    Ok(query) => query,
+                    Err(e) => {
+                        let _ = request.respond(error_resp(400, e));
+                        continue;
+                    }
+                };
+
                 let resp = match load_data(file_path) {
-                    Err(e) => json_resp(
-                        serde_json::json!({"error": e.to_string()}).to_string(),
-                        500,
-                    ),
-                    Ok(data) => match serde_json::to_string(&data) {
-                        Ok(json) => json_resp(json, 200),
-                        Err(e) => json_resp(
-                            serde_json::json!({"error": e.to_string()}).to_string(),
-                            500,
-                        ),
+                    Err(e) => tupp_error_resp(e),
+                    Ok(mut data) => {
+                        if let Some(query) = query {
+                            let mut keep = data.contacts.iter().map(|c| query.matches(c, &data)).collect::<Vec<_>>().into_iter();
+                            data.contacts.retain(|_| keep.next().unwrap_or(false));
+                        }
+                        if let Some((format, media)) = negotiated {
+                            let contacts: Vec<&Contact> = data.contacts.iter().collect();
+                            let body = formats::export(format, &contacts, &data, &Default::default());
+                            let _ = request.respond(match body {
+                                Ok(body) => typed_resp(body, 200, media),
+                                Err(e) => tupp_error_resp(e),
+                            });
+                            continue;
+                        }
+                        match serde_json::to_string(&data) {
+                            Ok(json) => json_resp(json, 200),
+                            Err(e) => error_resp(500, e),
+                        }
                     },
                 };
                 let _ = request.respond(resp);
@@ -107,25 +317,10 @@ pub fn handle_serve_command(port: u16, file_path: &PathBuf) -> Result<(), TuppEr
 
             // POST /contacts → create or update a contact
             Route::PostContacts => {
-                // Read body
-                let mut body = String::new();
-                if let Err(e) = request.as_reader().read_to_string(&mut body) {
-                    let _ = request.respond(json_resp(
-                        serde_json::json!({"error": e.to_string()}).to_string(),
-                        400,
-                    ));
-                    continue;
-                }
-
-                // Parse JSON
-                let mut value: serde_json::Value = match serde_json::from_str(&body) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        let _ = request.respond(json_resp(
-                            serde_json::json!({"error": format!("Invalid JSON: {}", e)})
-                                .to_string(),
-                            400,
-                        ));
+                let mut value = match read_json(&mut request) {
+                    Ok(value) => value,
+                    Err(resp) => {
+                        let _ = request.respond(resp);
                         continue;
                     }
                 };
@@ -137,115 +332,221 @@ pub fn handle_serve_command(port: u16, file_path: &PathBuf) -> Result<(), TuppEr
                 }
 
                 // Deserialize into Contact (validates required fields)
-                let contact: Contact = match serde_json::from_value(value) {
+                let mut contact: Contact = match serde_json::from_value(value) {
                     Ok(c) => c,
                     Err(e) => {
-                        let _ = request.respond(json_resp(
-                            serde_json::json!({"error": format!("Invalid contact: {}", e)})
-                                .to_string(),
-                            400,
-                        ));
+                        let _ = request.respond(error_resp(400, format!("Invalid contact: {}", e)));
                         continue;
                     }
                 };
 
                 let resp = match load_data(file_path) {
-                    Err(e) => json_resp(
-                        serde_json::json!({"error": e.to_string()}).to_string(),
-                        500,
-                    ),
+                    Err(e) => tupp_error_resp(e),
                     Ok(mut data) => {
+                        let contact_id = contact.identifier;
                         if is_update {
                             // Update existing contact
-                            match data
-                                .contacts
-                                .iter()
-                                .position(|c| c.identifier == contact.identifier)
-                            {
+                            match data.contacts.iter().position(|c| c.identifier == contact_id) {
                                 Some(pos) => {
+                                    contact.created = contact.created.or(data.contacts[pos].created);
+                                    contact.touch();
                                     data.contacts[pos] = contact;
-                                    // Collect links before mutable iteration
-                                    let contact_id = data.contacts[pos].identifier;
-                                    let links_to_mirror: Vec<(Uuid, _)> = data.contacts[pos]
-                                        .links
-                                        .as_ref()
-                                        .map(|ls| ls.iter().map(|l| (l.target, Contact::get_reciprocal_relation(&l.relation))).collect())
-                                        .unwrap_or_default();
-                                    // Add symmetric links to target contacts
-                                    for (target_id, reciprocal) in links_to_mirror {
-                                        if target_id == contact_id { continue; }
-                                        if let Some(target) = data.contacts.iter_mut().find(|c| c.identifier == target_id) {
-                                            let already_exists = target.links.as_ref()
-                                                .map(|ls| ls.iter().any(|l| l.target == contact_id))
-                                                .unwrap_or(false);
-                                            if !already_exists {
-                                                let new_link = Link { target: contact_id, relation: reciprocal };
-                                                target.links.get_or_insert_with(Vec::new).push(new_link);
-                                            }
-                                        }
-                                    }
+                                    mirror_links(&mut data, contact_id);
                                     match save_data(file_path, &data) {
-                                        Ok(_) => json_resp(
-                                            serde_json::json!({"status": "updated"}).to_string(),
-                                            200,
-                                        ),
-                                        Err(e) => json_resp(
-                                            serde_json::json!({"error": e.to_string()})
-                                                .to_string(),
-                                            500,
-                                        ),
+                                        Ok(_) => json_resp(serde_json::json!({"status": "updated"}).to_string(), 200),
+                                        Err(e) => tupp_error_resp(e),
                                     }
                                 }
-                                None => json_resp(
-                                    serde_json::json!({"error": "Contact not found"}).to_string(),
-                                    404,
-                                ),
+                                None => error_resp(404, "Contact not found"),
                             }
                         } else {
                             // Create new contact
-                            let id = contact.identifier.to_string();
-                            let contact_id = contact.identifier;
-                            // Collect links before inserting to avoid borrow issues
-                            let links_to_mirror: Vec<(Uuid, _)> = contact
-                                .links
-                                .as_ref()
-                                .map(|ls| ls.iter().map(|l| (l.target, Contact::get_reciprocal_relation(&l.relation))).collect())
-                                .unwrap_or_default();
+                            contact.created.get_or_insert_with(chrono::Utc::now);
                             data.contacts.push(contact);
-                            // Add symmetric links to target contacts
-                            for (target_id, reciprocal) in links_to_mirror {
-                                if let Some(target) = data.contacts.iter_mut().find(|c| c.identifier == target_id) {
-                                    let already_exists = target.links.as_ref()
-                                        .map(|ls| ls.iter().any(|l| l.target == contact_id))
-                                        .unwrap_or(false);
-                                    if !already_exists {
-                                        let new_link = Link { target: contact_id, relation: reciprocal };
-                                        target.links.get_or_insert_with(Vec::new).push(new_link);
+                            mirror_links(&mut data, contact_id);
+                            match save_data(file_path, &data) {
+                                Ok(_) => json_resp(serde_json::json!(contact_id.to_string()).to_string(), 201)
+                                    .with_header(location(contact_id)),
+                                Err(e) => tupp_error_resp(e),
+                            }
+                        }
+                    }
+                };
+
+                let _ = request.respond(resp);
+            }
+
+            // GET /contacts/{id} → one contact, as JSON or in the format of the Accept header
+            Route::GetContact(id) => {
+                let negotiated = negotiate(header(&request, "Accept").as_deref());
+                let resp = match load_data(file_path) {
+                    Err(e) => tupp_error_resp(e),
+                    Ok(data) => match (data.contacts.iter().find(|c| c.identifier == id), negotiated) {
+                        (None, _) => error_resp(404, "Contact not found"),
+                        (Some(contact), Some((format, media))) => match formats::export(format, &[contact], &data, &Default::default()) {
+                            Ok(body) => typed_resp(body, 200, media),
+                            Err(e) => tupp_error_resp(e),
+                        },
+                        (Some(contact), None) => contact_resp(contact, 200),
+                    },
+                };
+                let _ = request.respond(resp);
+            }
+
+            // PUT /contacts/{id} → replace a contact, or create it with this identifier
+            Route::PutContact(id) => {
+                let resp = match read_json(&mut request) {
+                    Err(resp) => resp,
+                    Ok(mut value) => {
+                        let body_id = value.get("identifier").and_then(Value::as_str).and_then(|i| Uuid::parse_str(i).ok());
+                        if value.get("identifier").is_some() && body_id != Some(id) {
+                            error_resp(400, "The identifier of the contact does not match the URL")
+                        } else {
+                            value["identifier"] = serde_json::json!(id.to_string());
+                            match serde_json::from_value::<Contact>(value) {
+                                Err(e) => error_resp(400, format!("Invalid contact: {}", e)),
+                                Ok(contact) => replace_contact(file_path, contact),
+                            }
+                        }
+                    },
+                };
+                let _ = request.respond(resp);
+            }
+
+            // PATCH /contacts/{id} → apply a JSON Merge Patch (RFC 7396) to a contact
+            Route::PatchContact(id) => {
+                let content_type = header(&request, "Content-Type").unwrap_or_default();
+                let media = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+                if !matches!(media.as_str(), "application/merge-patch+json" | "application/json") {
+                    let _ = request.respond(error_resp(415, "PATCH takes a JSON Merge Patch, as application/merge-patch+json"));
+                    continue;
+                }
+                let resp = match read_json(&mut request) {
+                    Err(resp) => resp,
+                    Ok(patch) => patch_contact(file_path, id, &patch),
+                };
+                let _ = request.respond(resp);
+            }
+
+            // DELETE /contacts/{id} → delete a contact and the links to it
+            Route::DeleteContact(id) => {
+                let resp = match load_data(file_path) {
+                    Err(e) => tupp_error_resp(e),
+                    Ok(mut data) => {
+                        let before = data.contacts.len();
+                        data.contacts.retain(|c| c.identifier != id);
+                        if data.contacts.len() == before {
+                            error_resp(404, "Contact not found")
+                        } else {
+                            for contact in &mut data.contacts {
+                                if let Some(links) = contact.links.as_mut() {
+                                    links.retain(|l| l.target != id);
+                                    if links.is_empty() {
+                                        contact.links = None;
                                     }
                                 }
                             }
                             match save_data(file_path, &data) {
-                                Ok(_) => json_resp(serde_json::json!(id).to_string(), 201),
-                                Err(e) => json_resp(
-                                    serde_json::json!({"error": e.to_string()}).to_string(),
-                                    500,
-                                ),
+                                Ok(_) => empty_resp(204),
+                                Err(e) => tupp_error_resp(e),
                             }
                         }
-                    }
+                    },
                 };
+                let _ = request.respond(resp);
+            }
 
+            // GET /calendar.ics → birthdays and anniversaries as yearly events
+            Route::GetCalendar => {
+                let resp = match load_data(file_path) {
+                    Ok(data) => {
+                        let contacts: Vec<&Contact> = data.contacts.iter().collect();
+                        typed_resp(formats::ics::write(&contacts), 200, "text/calendar; charset=utf-8")
+                    },
+                    Err(e) => tupp_error_resp(e),
+                };
+                let _ = request.respond(resp);
+            }
+
+            Route::InvalidId => {
+                let _ = request.respond(error_resp(400, "Invalid contact identifier, expected a UUID"));
+            }
+
+            Route::MethodNotAllowed(allowed) => {
+                let resp = error_resp(405, "Method not allowed")
+                    .with_header(Header::from_bytes("Allow", allowed).unwrap());
                 let _ = request.respond(resp);
             }
 
             Route::NotFound => {
-                let _ = request.respond(json_resp(
-                    serde_json::json!({"error": "Not found"}).to_string(),
-                    404,
-                ));
+                let _ = request.respond(error_resp(404, "Not found"));
             }
         }
     }
 
     Ok(())
 }
+
+/// Replace the contact with the identifier of `contact`, keeping its creation
+/// time, or add it: 200 with the contact, or 201 when it was created.
+fn replace_contact(file_path: &PathBuf, mut contact: Contact) -> Resp {
+    let mut data = match load_data(file_path) {
+        Ok(data) => data,
+        Err(e) => return tupp_error_resp(e),
+    };
+    let id = contact.identifier;
+    let status = match data.contacts.iter().position(|c| c.identifier == id) {
+        Some(pos) => {
+            contact.created = contact.created.or(data.contacts[pos].created);
+            contact.touch();
+            data.contacts[pos] = contact;
+            200
+        },
+        None => {
+            contact.created.get_or_insert_with(chrono::Utc::now);
+            data.contacts.push(contact);
+            201
+        },
+    };
+    save_contact(file_path, &mut data, id, status)
+}
+
+/// Apply a JSON Merge Patch to a contact: members of the patch replace those
+/// of the contact, objects are patched recursively, and `null` removes a field.
+fn patch_contact(file_path: &PathBuf, id: Uuid, patch: &Value) -> Resp {
+    let mut data = match load_data(file_path) {
+        Ok(data) => data,
+        Err(e) => return tupp_error_resp(e),
+    };
+    let Some(pos) = data.contacts.iter().position(|c| c.identifier == id) else {
+        return error_resp(404, "Contact not found");
+    };
+
+    let mut value = match serde_json::to_value(&data.contacts[pos]) {
+        Ok(value) => value,
+        Err(e) => return error_resp(500, e),
+    };
+    diff::merge_patch(&mut value, patch);
+    if value.get("identifier") != Some(&serde_json::json!(id.to_string())) {
+        return error_resp(400, "The contact identifier cannot be changed");
+    }
+    let mut contact: Contact = match serde_json::from_value(value) {
+        Ok(contact) => contact,
+        Err(e) => return error_resp(400, format!("Invalid contact: {}", e)),
+    };
+    contact.touch();
+    data.contacts[pos] = contact;
+    save_contact(file_path, &mut data, id, 200)
+}
+
+/// Mirror the links of a changed contact, save the data and answer with the contact.
+fn save_contact(file_path: &PathBuf, data: &mut TuppData, id: Uuid, status: u16) -> Resp {
+    mirror_links(data, id);
+    if let Err(e) = save_data(file_path, data) {
+        return tupp_error_resp(e);
+    }
+    match data.contacts.iter().find(|c| c.identifier == id) {
+        Some(contact) => contact_resp(contact, status),
+        None => error_resp(500, "Contact not found after saving"),
+    }
+}
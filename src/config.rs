@@ -0,0 +1,51 @@
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::OnceLock;
+use crate::error::TuppError;
+use crate::storage::get_config_dir;
+
+/// User preferences, read from `config.json` next to the data file.
+/// Every setting is optional and falls back to its default.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Minimum similarity (0 to 1) for a fuzzy search match.
+    pub fuzzy_threshold: f64,
+    /// Named name templates, usable wherever a name format is expected.
+    /// They take precedence over the built-in presets of the same name.
+    pub name_formats: BTreeMap<String, String>,
+    /// Country calling code given to imported numbers written without one.
+    pub default_country_code: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            fuzzy_threshold: 0.7,
+            name_formats: BTreeMap::new(),
+            default_country_code: 33,
+        }
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub fn load_config() -> Result<Config, TuppError> {
+    let path = get_config_dir()?.join("config.json");
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let content = fs::read_to_string(&path).map_err(TuppError::Io)?;
+    serde_json::from_str(&content).map_err(TuppError::Serialization)
+}
+
+/// Make the configuration available through `config()`. Only the first call has an effect.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// The user configuration, or the defaults if it was never loaded.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
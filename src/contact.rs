@@ -1,9 +1,13 @@
-use serde::{ Deserialize, Serialize };
+use serde::{ Deserialize, Serialize, Serializer };
 use std::fmt;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
 
 use crate::models::*;
+use crate::matching::{self, MatchKind};
+use crate::sanitize::fold;
+use crate::name_format::{NameFormat, FIRST_LAST, FULL_NAME, LAST_FIRST};
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Contact {
@@ -13,8 +17,18 @@ pub struct Contact {
     pub emails: Option<Vec<Email>>,
     pub phones: Option<Vec<PhoneNumber>>,
     pub socials: Option<Vec<Social>>,
+    #[serde(serialize_with = "sorted_groups")]
     pub groups: Option<HashSet<Uuid>>,
     pub links: Option<Vec<Link>>,
+    pub sources: Option<Vec<Source>>,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+}
+
+/// Write the groups of a contact in a stable order, so that unchanged
+/// contacts serialize the same way and data files can be compared.
+fn sorted_groups<S: Serializer>(groups: &Option<HashSet<Uuid>>, serializer: S) -> Result<S::Ok, S::Error> {
+    groups.as_ref().map(|groups| groups.iter().collect::<BTreeSet<_>>()).serialize(serializer)
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -36,48 +50,147 @@ pub enum Relation {
     Ex
 }
 
+/// Which part of a contact a search query matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchField {
+    Social,
+    Phone,
+    Email,
+    Name,
+}
+
+#[derive(Debug)]
+pub struct SearchResult<'a> {
+    pub contact: &'a Contact,
+    pub kind: MatchKind,
+    pub field: MatchField,
+}
+
+impl SearchResult<'_> {
+    /// Results are ranked by match kind first, then by the field matched.
+    pub fn score(&self) -> (MatchKind, MatchField) {
+        (self.kind, self.field)
+    }
+}
+
 impl Contact {
-    pub fn find_best_match<'a>(contacts: &'a [Contact], text: &str) -> Option<&'a Contact> {
+    /// A new contact with nothing but an identity.
+    pub fn new(identity: Identity) -> Self {
+        Self {
+            identifier: Uuid::new_v4(),
+            identity,
+            address: None,
+            emails: None,
+            phones: None,
+            socials: None,
+            groups: None,
+            links: None,
+            sources: None,
+            created: Some(Utc::now()),
+            updated: None,
+        }
+    }
+
+    /// Record that the contact was just modified.
+    pub fn touch(&mut self) {
+        self.updated = Some(Utc::now());
+    }
+
+    /// Search contacts by name, email, phone number or social handle,
+    /// returning every match ranked from best to worst. Case and accents
+    /// are ignored, and small typos are tolerated.
+    /// A UUID only matches the contact with that identifier.
+    pub fn search<'a>(contacts: &'a [Contact], text: &str) -> Vec<SearchResult<'a>> {
         if let Ok(id) = Uuid::parse_str(text) {
-            return contacts.iter().find(|c| c.identifier == id);
+            return contacts
+                .iter()
+                .filter(|c| c.identifier == id)
+                .map(|contact| SearchResult { contact, kind: MatchKind::Exact, field: MatchField::Name })
+                .collect();
         }
 
-        let closure_score = |contact: &Contact| -> i32 {
-            let name_score = if contact
-                .format_name("TITLE FIRST MIDDLE LAST POST")
-                .to_lowercase()
-                .contains(
-                    &crate::sanitize::trim_extra_spaces(text)
-                    .to_lowercase()
-                ) { 1 } else { 0 };
-            
-            return name_score
-        };
-        
-        let best_match = contacts
+        let query = fold(text);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let threshold = crate::config::config().fuzzy_threshold;
+
+        let mut results: Vec<SearchResult> = contacts
             .iter()
-            .max_by_key(|contact| { closure_score(contact) })?;
+            .filter_map(|contact| {
+                contact
+                    .search_values()
+                    .into_iter()
+                    .filter_map(|(field, value)| Some((match_value(&query, &fold(&value), threshold)?, field)))
+                    .max()
+                    .map(|(kind, field)| SearchResult { contact, kind, field })
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score().cmp(&a.score())
+                .then_with(|| a.contact.format_name(&LAST_FIRST).cmp(&b.contact.format_name(&LAST_FIRST)))
+        });
+        results
+    }
 
-        if closure_score(best_match) > 0 {
-            return Some(best_match);
-        } else { return None };
+    /// Return the contact a query names without doubt: the one with this
+    /// identifier, or the only one matching it exactly. Partial and fuzzy
+    /// matches give `None`, as a typo could name an unrelated contact.
+    pub fn find_best_match<'a>(contacts: &'a [Contact], text: &str) -> Option<&'a Contact> {
+        let results = Self::search(contacts, text);
+        match results.as_slice() {
+            [first, rest @ ..] if first.kind == MatchKind::Exact && rest.iter().all(|r| r.score() < first.score()) => {
+                Some(first.contact)
+            },
+            _ => None,
+        }
     }
 
-    pub fn format_name(&self, pattern: &str) -> String {
-        let title = self.identity.title.clone().unwrap_or_default();
-        let first_name = self.identity.first_name.clone().unwrap_or_default();
-        let middle_name = self.identity.middle_name.clone().unwrap_or_default();
-        let last_name = self.identity.last_name.clone().unwrap_or_default();
-        let post_nominal = self.identity.post_nominal.clone().unwrap_or_default();
-
-        return crate::sanitize::trim_extra_spaces(
-            &pattern
-                .replace("TITLE", &title)
-                .replace("FIRST", &first_name)
-                .replace("MIDDLE", &middle_name)
-                .replace("LAST", &last_name)
-                .replace("POST", &post_nominal)
-        );
+    fn search_values(&self) -> Vec<(MatchField, String)> {
+        let identity = &self.identity;
+        let mut values = vec![
+            (MatchField::Name, self.format_name(&FULL_NAME)),
+            (MatchField::Name, self.format_name(&FIRST_LAST)),
+            (MatchField::Name, self.format_name(&LAST_FIRST)),
+        ];
+
+        for name in [
+            &identity.first_name,
+            &identity.middle_name,
+            &identity.last_name,
+            &identity.birth_first_name,
+            &identity.birth_middle_name,
+            &identity.birth_last_name,
+        ].into_iter().flatten() {
+            values.push((MatchField::Name, name.clone()));
+        }
+
+        for email in self.emails.iter().flatten() {
+            if let Some(address) = &email.address {
+                values.push((MatchField::Email, address.clone()));
+            }
+        }
+
+        for phone in self.phones.iter().flatten() {
+            values.push((MatchField::Phone, format!("+{}{}", phone.country_code, phone.number)));
+            values.push((MatchField::Phone, phone.number.to_string()));
+            values.push((MatchField::Phone, format!("0{}", phone.number)));
+        }
+
+        for social in self.socials.iter().flatten() {
+            if let Some(username) = &social.username {
+                values.push((MatchField::Social, username.clone()));
+            }
+        }
+
+        values
+    }
+
+    /// Format the name with a format resolved once by the caller, see
+    /// `NameFormat::resolve`, or a built-in one such as `name_format::FIRST_LAST`.
+    pub fn format_name(&self, format: &NameFormat) -> String {
+        format.render(&self.identity)
     }
 
     pub fn create_bidirectional_link(
@@ -121,6 +234,26 @@ impl Contact {
         }
     }
 
+    /// Remove the links between two contacts, on both sides.
+    /// Returns whether any link existed.
+    pub fn remove_bidirectional_link(contact_a: &mut Contact, contact_b: &mut Contact) -> bool {
+        let mut removed = false;
+        let (id_a, id_b) = (contact_a.identifier, contact_b.identifier);
+
+        for (from, to) in [(contact_a, id_b), (contact_b, id_a)] {
+            if let Some(ref mut links_vec) = from.links {
+                let initial_len = links_vec.len();
+                links_vec.retain(|link| link.target != to);
+                removed |= links_vec.len() < initial_len;
+                if links_vec.is_empty() {
+                    from.links = None;
+                }
+            }
+        }
+
+        removed
+    }
+
     fn parse_relation(relation_str: &str) -> Option<Relation> {
         match relation_str.to_lowercase().as_str() {
             "friend" => Some(Relation::Friend),
@@ -152,6 +285,31 @@ impl Contact {
 
 }
 
+fn match_value(query: &str, value: &str, threshold: f64) -> Option<MatchKind> {
+    // Phone numbers are compared on their digits only, and never fuzzily.
+    // Only queries written like a phone number can match one: "x1" is not "1".
+    if value.starts_with('+') || value.chars().all(|c| c.is_ascii_digit()) {
+        let phone_like = query.chars().all(|c| c.is_ascii_digit() || "+ -./()".contains(c));
+        if !phone_like {
+            return None;
+        }
+        let compact: String = query.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+        return matching::match_kind(&compact, value, 1.1);
+    }
+    matching::match_kind(query, value, threshold)
+}
+
+impl fmt::Display for MatchField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchField::Name => write!(f, "name"),
+            MatchField::Email => write!(f, "email"),
+            MatchField::Phone => write!(f, "phone"),
+            MatchField::Social => write!(f, "social"),
+        }
+    }
+}
+
 impl fmt::Display for Contact {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
@@ -219,6 +377,14 @@ impl fmt::Display for Contact {
             )?;
         }
 
+        if let Some(anniversary) = &self.identity.anniversary {
+            writeln!(
+                f,
+                "\tAnniversary: {}",
+                anniversary
+            )?;
+        }
+
         if !self.identity.is_alive {
             if let Some(death_date) = &self.identity.death_date {
                 writeln!(
@@ -321,3 +487,40 @@ impl fmt::Display for Link {
         write!(f, "{}: {}", self.relation, self.target)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phones_match_queries_written_like_phone_numbers() {
+        assert_eq!(match_value("+33 6 12-34", "+33612345678", 0.8), Some(MatchKind::Prefix));
+        assert_eq!(match_value("(0)6.12", "0612345678", 0.8), Some(MatchKind::Prefix));
+        assert_eq!(match_value("34 56", "0612345678", 0.8), Some(MatchKind::Substring));
+        assert_eq!(match_value("x1", "+33612345678", 0.8), None);
+        assert_eq!(match_value("anna 06", "0612345678", 0.8), None);
+    }
+
+    fn named(first: &str, last: &str) -> Contact {
+        Contact::new(Identity {
+            first_name: Some(first.to_string()),
+            last_name: Some(last.to_string()),
+            ..Identity::default()
+        })
+    }
+
+    #[test]
+    fn only_exact_matches_resolve_a_contact() {
+        let contacts = vec![named("Ada", "Lovelace"), named("Alan", "Turing")];
+        let found = |text: &str| Contact::find_best_match(&contacts, text).map(|c| c.identifier);
+
+        assert_eq!(found("Ada Lovelace"), Some(contacts[0].identifier));
+        assert_eq!(found("turing"), Some(contacts[1].identifier));
+        assert_eq!(found(&contacts[1].identifier.to_string()), Some(contacts[1].identifier));
+        assert_eq!(found("lovelace"), Some(contacts[0].identifier));
+        // A typo or a prefix could name an unrelated contact
+        assert_eq!(found("Lovelase"), None);
+        assert_eq!(found("Lov"), None);
+        assert_eq!(found("Grace"), None);
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Serialize;
+use uuid::Uuid;
+use crate::contact::Contact;
+use crate::sanitize::fold;
+use crate::name_format::FIRST_LAST;
+
+/// A pair of contacts that likely describe the same person.
+#[derive(Serialize, Debug)]
+pub struct Duplicate {
+    pub a: Uuid,
+    pub b: Uuid,
+    pub a_name: String,
+    pub b_name: String,
+    pub score: f64,
+    pub reasons: Vec<String>,
+}
+
+const SAME_NAME: f64 = 0.6;
+const SWAPPED_NAME: f64 = 0.5;
+const SHARED_EMAIL: f64 = 0.5;
+const SHARED_PHONE: f64 = 0.4;
+const SAME_BIRTH_DATE: f64 = 0.3;
+
+/// Compare every pair of contacts and return those scoring at least `min_score`,
+/// most likely duplicates first.
+///
+/// Each matching signal has a weight, and weights are combined as independent
+/// evidence (`1 - (1 - w1)(1 - w2)...`), so the score stays between 0 and 1.
+pub fn find_duplicates(contacts: &[Contact], min_score: f64) -> Vec<Duplicate> {
+    let keys: Vec<Keys> = contacts.iter().map(Keys::new).collect();
+    let mut duplicates = Vec::new();
+
+    for i in 0..contacts.len() {
+        for j in (i + 1)..contacts.len() {
+            let (score, reasons) = compare(&keys[i], &keys[j]);
+            if score >= min_score && !reasons.is_empty() {
+                duplicates.push(Duplicate {
+                    a: contacts[i].identifier,
+                    b: contacts[j].identifier,
+                    a_name: contacts[i].format_name(&FIRST_LAST),
+                    b_name: contacts[j].format_name(&FIRST_LAST),
+                    score: (score * 100.0).round() / 100.0,
+                    reasons,
+                });
+            }
+        }
+    }
+
+    duplicates.sort_by(|x, y| y.score.total_cmp(&x.score));
+    duplicates
+}
+
+/// Score how likely two contacts are the same person, with the reasons why.
+pub fn score(a: &Contact, b: &Contact) -> (f64, Vec<String>) {
+    compare(&Keys::new(a), &Keys::new(b))
+}
+
+/// Normalized values of a contact used for comparison.
+struct Keys {
+    first: String,
+    last: String,
+    emails: Vec<String>,
+    phones: Vec<(u16, u64)>,
+    birth_date: Option<(i32, u8, u8)>,
+}
+
+impl Keys {
+    fn new(contact: &Contact) -> Self {
+        let identity = &contact.identity;
+        Self {
+            first: fold(identity.first_name.as_deref().unwrap_or_default()),
+            last: fold(identity.last_name.as_deref().unwrap_or_default()),
+            emails: contact.emails.iter().flatten()
+                .filter_map(|e| e.address.as_deref())
+                .map(|a| a.trim().to_lowercase())
+                .filter(|a| !a.is_empty())
+                .collect(),
+            phones: contact.phones.iter().flatten()
+                .map(|p| (p.country_code, p.number))
+                .collect(),
+            birth_date: identity.birth_date.as_ref()
+                .and_then(|d| Some((d.year?, d.month?, d.day?))),
+        }
+    }
+}
+
+fn compare(a: &Keys, b: &Keys) -> (f64, Vec<String>) {
+    let mut weights = Vec::new();
+    let mut reasons = Vec::new();
+
+    if !a.first.is_empty() && !a.last.is_empty() {
+        if a.first == b.first && a.last == b.last {
+            weights.push(SAME_NAME);
+            reasons.push("same name".to_string());
+        } else if a.first == b.last && a.last == b.first {
+            weights.push(SWAPPED_NAME);
+            reasons.push("same name, first and last swapped".to_string());
+        }
+    }
+
+    if let Some(email) = a.emails.iter().find(|e| b.emails.contains(e)) {
+        weights.push(SHARED_EMAIL);
+        reasons.push(format!("shared email {}", email));
+    }
+
+    if let Some((cc, number)) = a.phones.iter().find(|p| b.phones.contains(p)) {
+        weights.push(SHARED_PHONE);
+        reasons.push(format!("shared phone +{} {}", cc, number));
+    }
+
+    if a.birth_date.is_some() && a.birth_date == b.birth_date {
+        weights.push(SAME_BIRTH_DATE);
+        reasons.push("same birth date".to_string());
+    }
+
+    let score = 1.0 - weights.iter().map(|w| 1.0 - w).product::<f64>();
+    (score, reasons)
+}
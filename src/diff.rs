@@ -0,0 +1,289 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use uuid::Uuid;
+use crate::group::Group;
+use crate::models::TuppData;
+use crate::name_format::FIRST_LAST;
+use crate::error::TuppError;
+
+/// A single field-level difference between two JSON documents, keyed by its
+/// dotted path (e.g. `identity.first_name` or `emails.0.address`).
+#[derive(Debug)]
+pub enum Change {
+    Added(String, Value),
+    Removed(String, Value),
+    Modified(String, Value, Value),
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added(path, _) | Change::Removed(path, _) | Change::Modified(path, _, _) => path,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(path, new) => write!(f, "+ {}: {}", path, new),
+            Change::Removed(path, old) => write!(f, "- {}: {}", path, old),
+            Change::Modified(path, old, new) => write!(f, "~ {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+/// Flatten a JSON value into a map of dotted paths to leaf values.
+/// Null leaves are skipped so that a missing field and a `null` one compare equal.
+pub fn flatten(value: &Value) -> BTreeMap<String, Value> {
+    let mut out = BTreeMap::new();
+    flatten_into(value, String::new(), &mut out);
+    out
+}
+
+fn flatten_into(value: &Value, prefix: String, out: &mut BTreeMap<String, Value>) {
+    let join = |key: &str| {
+        if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) }
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                flatten_into(v, join(key), out);
+            }
+        },
+        Value::Array(items) => {
+            for (index, v) in items.iter().enumerate() {
+                flatten_into(v, join(&index.to_string()), out);
+            }
+        },
+        Value::Null => {},
+        leaf => {
+            out.insert(prefix, leaf.clone());
+        },
+    }
+}
+
+/// Compute the field-level changes needed to go from `old` to `new`.
+pub fn changes(old: &Value, new: &Value) -> Vec<Change> {
+    let old_flat = flatten(old);
+    let new_flat = flatten(new);
+    let mut result = Vec::new();
+
+    for (path, old_value) in &old_flat {
+        match new_flat.get(path) {
+            None => result.push(Change::Removed(path.clone(), old_value.clone())),
+            Some(new_value) if new_value != old_value => {
+                result.push(Change::Modified(path.clone(), old_value.clone(), new_value.clone()))
+            },
+            _ => {},
+        }
+    }
+
+    for (path, new_value) in &new_flat {
+        if !old_flat.contains_key(path) {
+            result.push(Change::Added(path.clone(), new_value.clone()));
+        }
+    }
+
+    result.sort_by(|a, b| a.path().cmp(b.path()));
+    result
+}
+
+/// Apply a JSON Merge Patch (RFC 7396) to `target`: members of an object
+/// patch are merged recursively, `null` members are removed, and any other
+/// patch replaces the target.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(map) = target {
+        for (key, value) in members {
+            if value.is_null() {
+                map.remove(key);
+            } else {
+                merge_patch(map.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// A contact or a group of a data file, as JSON. Groups are flattened, their
+/// `subgroups` replaced by the identifier of their `parent`, so that moving a
+/// group is a change of one field.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub kind: EntryKind,
+    pub identifier: Uuid,
+    pub name: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    Contact,
+    Group,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            EntryKind::Contact => "contact",
+            EntryKind::Group => "group",
+        };
+        write!(f, "{} {} ({})", kind, self.name, self.identifier)
+    }
+}
+
+/// The contacts, then the groups of a data file, as entries.
+pub fn entries(data: &TuppData) -> Result<Vec<Entry>, TuppError> {
+    let mut entries = Vec::new();
+    for contact in &data.contacts {
+        entries.push(Entry {
+            kind: EntryKind::Contact,
+            identifier: contact.identifier,
+            name: contact.format_name(&FIRST_LAST),
+            value: serde_json::to_value(contact)?,
+        });
+    }
+    flatten_groups(&data.groups, None, &mut entries);
+    Ok(entries)
+}
+
+fn flatten_groups(groups: &[Group], parent: Option<Uuid>, out: &mut Vec<Entry>) {
+    for group in groups {
+        out.push(Entry {
+            kind: EntryKind::Group,
+            identifier: group.identifier,
+            name: group.name.clone(),
+            value: json!({ "identifier": group.identifier, "name": group.name, "parent": parent }),
+        });
+        flatten_groups(&group.subgroups, Some(group.identifier), out);
+    }
+}
+
+/// Rebuild a data file from its entries. Groups whose parent is missing are
+/// put at the top level.
+pub fn from_entries(entries: Vec<Entry>) -> Result<TuppData, TuppError> {
+    let mut contacts = Vec::new();
+    let mut groups: Vec<(Option<Uuid>, Group)> = Vec::new();
+    for entry in entries {
+        match entry.kind {
+            EntryKind::Contact => contacts.push(serde_json::from_value(entry.value)?),
+            EntryKind::Group => {
+                let name = entry.value.get("name").and_then(Value::as_str).unwrap_or(&entry.name).to_string();
+                let parent = entry.value.get("parent").and_then(Value::as_str).and_then(|p| Uuid::parse_str(p).ok());
+                groups.push((parent, Group { identifier: entry.identifier, name, subgroups: Vec::new() }));
+            },
+        }
+    }
+
+    let ids: Vec<Uuid> = groups.iter().map(|(_, g)| g.identifier).collect();
+    let (children, top): (Vec<_>, Vec<_>) = groups
+        .into_iter()
+        .partition(|(parent, group)| parent.is_some_and(|p| p != group.identifier && ids.contains(&p)));
+    let mut tree: Vec<Group> = top.into_iter().map(|(_, g)| g).collect();
+
+    // Attach children once their parent is in the tree; those left in a cycle go to the top
+    let mut pending = children;
+    while !pending.is_empty() {
+        let before = pending.len();
+        let mut rest = Vec::new();
+        for (parent, group) in pending {
+            if let Some(parent) = parent.filter(|p| Group::contains_id_recursive(&tree, p)) {
+                Group::find_parent_and_add_recursive(&mut tree, &parent, group);
+            } else {
+                rest.push((parent, group));
+            }
+        }
+        if rest.len() == before {
+            tree.extend(rest.into_iter().map(|(_, g)| g));
+            break;
+        }
+        pending = rest;
+    }
+
+    Ok(TuppData { contacts, groups: tree })
+}
+
+/// How an entry differs between two data files.
+#[derive(Debug)]
+pub enum EntryChange {
+    Added,
+    Removed,
+    Modified(Vec<Change>),
+}
+
+/// The entries that differ from `old` to `new`, matched by identifier: those
+/// of `old`, in order, then those only in `new`.
+pub fn diff_data(old: &TuppData, new: &TuppData) -> Result<Vec<(Entry, EntryChange)>, TuppError> {
+    let old_entries = entries(old)?;
+    let new_entries = entries(new)?;
+    let find = |entries: &[Entry], entry: &Entry| {
+        entries.iter().find(|e| e.kind == entry.kind && e.identifier == entry.identifier).cloned()
+    };
+
+    let mut result = Vec::new();
+    for entry in &old_entries {
+        match find(&new_entries, entry) {
+            None => result.push((entry.clone(), EntryChange::Removed)),
+            Some(new_entry) => {
+                let changes = changes(&entry.value, &new_entry.value);
+                if !changes.is_empty() {
+                    result.push((new_entry, EntryChange::Modified(changes)));
+                }
+            },
+        }
+    }
+    for entry in new_entries {
+        if find(&old_entries, &entry).is_none() {
+            result.push((entry, EntryChange::Added));
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::merge_data;
+
+    /// A data file with a contact in several groups. Each load gives its
+    /// own `HashSet` of groups, iterated in its own order.
+    fn load() -> TuppData {
+        let groups: Vec<Group> = (1..=8).map(|i| Group::new(format!("Group {}", i))).collect();
+        let ids: Vec<Uuid> = groups.iter().map(|g| g.identifier).collect();
+        let data = json!({
+            "contacts": [{
+                "identifier": Uuid::new_v4(),
+                "identity": { "first_name": "Ada", "last_name": "Lovelace", "is_alive": false },
+                "groups": ids,
+            }],
+            "groups": groups,
+        });
+        let text = data.to_string();
+        serde_json::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn identical_data_has_no_differences() {
+        let data = load();
+        let copy: TuppData = serde_json::from_value(serde_json::to_value(&data).unwrap()).unwrap();
+        assert!(diff_data(&data, &copy).unwrap().is_empty());
+    }
+
+    #[test]
+    fn merging_identical_data_changes_nothing() {
+        let data = load();
+        let copy: TuppData = serde_json::from_value(serde_json::to_value(&data).unwrap()).unwrap();
+        let mut resolve = |_: &str, _: &str, _: &str| panic!("no conflict expected");
+        let (merged, conflicts) = merge_data(Some(&data), &data, &copy, &mut resolve).unwrap();
+        assert!(conflicts.is_empty());
+        assert!(diff_data(&data, &merged).unwrap().is_empty());
+    }
+}
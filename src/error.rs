@@ -22,6 +22,12 @@ pub enum TuppError {
     #[error("Duplicate entry: {0}")]
     Duplicate(String),
 
+    #[error("Invalid query: {0}")]
+    Query(String),
+
+    #[error("Ambiguous query: {0}")]
+    Ambiguous(String),
+
     #[error("Unknown error: {0}")]
     Other(String),
 }
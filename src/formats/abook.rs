@@ -0,0 +1,151 @@
+use std::path::Path;
+use crate::contact::Contact;
+use crate::import::{read_text, Fragment, Imported, Importer};
+use crate::merge::unique_label;
+use crate::models::{Address, Email, Identity, PhoneNumber};
+use crate::error::TuppError;
+
+/// The contact field of each imported abook field, for the mapping report.
+const TARGETS: &[(&str, &str)] = &[
+    ("name", "name"), ("email", "emails"), ("phone", "phones"), ("workphone", "phones"), ("mobile", "phones"),
+    ("address", "address"), ("address2", "address"), ("city", "address"), ("state", "address"), ("zip", "address"),
+    ("country", "address"), ("anniversary", "anniversary"), ("groups", "groups"),
+];
+
+/// Labels of the phone fields.
+const PHONE_FIELDS: &[(&str, &str)] = &[("phone", "home"), ("workphone", "work"), ("mobile", "mobile")];
+
+/// Reads the `addressbook` file of abook, an INI file with a section per contact.
+pub struct AbookImporter;
+
+impl Importer for AbookImporter {
+    fn name(&self) -> &'static str {
+        "abook"
+    }
+
+    fn read(&self, path: &Path, default_country_code: u16) -> Result<Fragment, TuppError> {
+        read(&read_text(path)?, default_country_code)
+    }
+}
+
+/// Read the sections of an abook file as contacts. abook has no identifiers,
+/// so contacts are keyed by their name and first email.
+pub fn read(text: &str, default_country_code: u16) -> Result<Fragment, TuppError> {
+    let mut fragment = Fragment::default();
+    for entry in parse(text)? {
+        for (field, value) in &entry {
+            if !value.is_empty() {
+                let target = TARGETS.iter().find(|(name, _)| name == field).map(|(_, target)| *target);
+                fragment.report.record(field, target);
+            }
+        }
+        fragment.contacts.push(to_contact(&entry, default_country_code));
+    }
+    Ok(fragment)
+}
+
+/// Split an abook file into the fields of each contact section, leaving out
+/// the `[format]` section.
+fn parse(text: &str) -> Result<Vec<Vec<(String, String)>>, TuppError> {
+    let mut entries = Vec::new();
+    let mut entry: Option<Vec<(String, String)>> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            entries.extend(entry.take().filter(|e| !e.is_empty()));
+            if section.trim() != "format" {
+                entry = Some(Vec::new());
+            }
+            continue;
+        }
+
+        let (field, value) = line
+            .split_once('=')
+            .ok_or_else(|| TuppError::Validation(format!("Line {}: expected 'field=value'", number + 1)))?;
+        if let Some(entry) = entry.as_mut() {
+            entry.push((field.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    entries.extend(entry.filter(|e| !e.is_empty()));
+
+    Ok(entries)
+}
+
+fn to_contact(entry: &[(String, String)], default_country_code: u16) -> Imported {
+    let field = |name: &str| {
+        entry.iter()
+            .find(|(f, _)| f == name)
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    };
+
+    let mut identity = Identity::default();
+    if let Some(name) = field("name") {
+        let mut words = name.split_whitespace();
+        identity.first_name = words.next().map(str::to_string);
+        identity.last_name = Some(words.collect::<Vec<_>>().join(" ")).filter(|r| !r.is_empty());
+    }
+
+    let mut unmapped = Vec::new();
+    let mut emails: Vec<Email> = Vec::new();
+    for address in field("email").iter().flat_map(|e| e.split(',')).map(str::trim).filter(|a| !a.is_empty()) {
+        let label = unique_label(Some("default".to_string()), emails.iter().map(|e| e.label.as_deref()));
+        emails.push(Email { label, address: Some(address.to_string()) });
+    }
+
+    let mut phones: Vec<PhoneNumber> = Vec::new();
+    for (name, label) in PHONE_FIELDS {
+        let Some(number) = field(name) else { continue };
+        let label = unique_label(Some(label.to_string()), phones.iter().map(|p| p.label.as_deref()));
+        match PhoneNumber::parse(label, &number, default_country_code) {
+            Some(phone) => phones.push(phone),
+            None => unmapped.push(format!("{} ({})", name, number)),
+        }
+    }
+
+    let street = [field("address"), field("address2")].into_iter().flatten().collect::<Vec<_>>().join(", ");
+    let (number, street) = super::split_street(Some(street).filter(|s| !s.is_empty()));
+    let address = Address {
+        label: None,
+        country: field("country"),
+        region: field("state"),
+        city: field("city"),
+        post_code: field("zip"),
+        street,
+        number,
+    };
+    let has_address = address.street.is_some() || address.city.is_some() || address.post_code.is_some()
+        || address.region.is_some() || address.country.is_some();
+
+    let anniversary = field("anniversary");
+    let anniversary_date = anniversary.as_deref().and_then(super::vcard::parse_date);
+    if let (Some(text), None) = (&anniversary, &anniversary_date) {
+        unmapped.push(format!("anniversary '{}'", text));
+    }
+
+    let groups = field("groups")
+        .iter()
+        .flat_map(|g| g.split(','))
+        .map(str::trim)
+        .filter(|g| !g.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    for (name, value) in entry {
+        if !value.is_empty() && !TARGETS.iter().any(|(target, _)| target == name) {
+            unmapped.push(name.clone());
+        }
+    }
+
+    identity.anniversary = anniversary_date;
+    let mut contact = Contact::new(identity);
+    contact.address = Some(address).filter(|_| has_address);
+    contact.emails = Some(emails).filter(|v| !v.is_empty());
+    contact.phones = Some(phones).filter(|v| !v.is_empty());
+
+    Imported::new(contact, groups, unmapped)
+}
@@ -0,0 +1,706 @@
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+use crate::contact::Contact;
+use crate::group::Group;
+use crate::import::{read_text, Fragment, Imported, Importer};
+use crate::merge::unique_label;
+use crate::name_format::NameFormat;
+use crate::models::{Address, Date, Email, Gender, Identity, PhoneNumber, Social, TuppData};
+use crate::output::csv_escape;
+use crate::error::TuppError;
+
+/// A part of an address a column can hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AddressPart {
+    Label,
+    Number,
+    Street,
+    City,
+    Region,
+    PostCode,
+    Country,
+}
+
+/// The contact field a column holds. Emails, phones and addresses are
+/// grouped by slot: `email.1` and `email.1.label` describe the same email,
+/// and a slot that is not a number is also the default label (`phone.mobile`).
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Ignore,
+    Id,
+    Name,
+    Title,
+    First,
+    Middle,
+    Last,
+    Post,
+    Gender,
+    Birth,
+    Anniversary,
+    Death,
+    Groups,
+    Email { slot: String, label: bool },
+    Phone { slot: String, label: bool },
+    Address { slot: String, part: AddressPart },
+    Social(String),
+}
+
+impl Target {
+    /// Parse a target as written in mapping files, e.g. `first`,
+    /// `email.work`, `phone.1.label` or `address.home.city`.
+    fn parse(text: &str) -> Result<Self, TuppError> {
+        let text = text.trim().to_lowercase();
+        let parts: Vec<&str> = text.split('.').collect();
+        let target = match parts.as_slice() {
+            [] | [""] | ["-"] | ["ignore"] => Target::Ignore,
+            ["id"] => Target::Id,
+            ["name"] => Target::Name,
+            ["title"] => Target::Title,
+            ["first"] => Target::First,
+            ["middle"] => Target::Middle,
+            ["last"] => Target::Last,
+            ["post"] => Target::Post,
+            ["gender"] => Target::Gender,
+            ["birth"] => Target::Birth,
+            ["anniversary"] => Target::Anniversary,
+            ["death"] => Target::Death,
+            ["groups"] => Target::Groups,
+            ["email", slot] => Target::Email { slot: slot.to_string(), label: false },
+            ["email", slot, "label"] => Target::Email { slot: slot.to_string(), label: true },
+            ["phone", slot] => Target::Phone { slot: slot.to_string(), label: false },
+            ["phone", slot, "label"] => Target::Phone { slot: slot.to_string(), label: true },
+            ["address", slot, part] => {
+                let part = match *part {
+                    "label" => AddressPart::Label,
+                    "number" => AddressPart::Number,
+                    "street" => AddressPart::Street,
+                    "city" => AddressPart::City,
+                    "region" => AddressPart::Region,
+                    "post_code" => AddressPart::PostCode,
+                    "country" => AddressPart::Country,
+                    other => return Err(TuppError::Validation(format!("Unknown address part '{}' in '{}'", other, text))),
+                };
+                Target::Address { slot: slot.to_string(), part }
+            },
+            ["social", network] => Target::Social(network.to_string()),
+            _ => return Err(TuppError::Validation(format!("Unknown column target '{}'", text))),
+        };
+        Ok(target)
+    }
+
+    /// Recognize the headers of Google Contacts and Outlook exports, and
+    /// headers that are targets themselves.
+    fn detect(header: &str) -> Option<Self> {
+        let header = header.trim();
+        let target = match header.to_lowercase().as_str() {
+            "name" | "full name" | "display name" => "name",
+            "first name" | "given name" => "first",
+            "middle name" | "additional name" => "middle",
+            "last name" | "family name" => "last",
+            "name prefix" | "title" => "title",
+            "name suffix" | "suffix" => "post",
+            "gender" => "gender",
+            "birthday" => "birth",
+            "anniversary" => "anniversary",
+            "death date" => "death",
+            "labels" | "group membership" | "categories" => "groups",
+            "e-mail address" => "email.1",
+            "e-mail 2 address" => "email.2",
+            "e-mail 3 address" => "email.3",
+            "mobile phone" => "phone.mobile",
+            "home phone" => "phone.home",
+            "home phone 2" => "phone.home-2",
+            "business phone" => "phone.work",
+            "business phone 2" => "phone.work-2",
+            "other phone" => "phone.other",
+            "primary phone" => "phone.primary",
+            _ => return Self::detect_numbered(header)
+                .or_else(|| Self::detect_outlook_address(header))
+                .or_else(|| Self::parse(header).ok().filter(|t| *t != Target::Ignore)),
+        };
+        Self::parse(target).ok()
+    }
+
+    /// The contact field of the target, for the mapping report.
+    fn field(&self) -> Option<&'static str> {
+        Some(match self {
+            Target::Ignore => return None,
+            Target::Id => "identifier",
+            Target::Name | Target::Title | Target::First | Target::Middle | Target::Last | Target::Post => "name",
+            Target::Gender => "gender",
+            Target::Birth => "birth date",
+            Target::Anniversary => "anniversary",
+            Target::Death => "death date",
+            Target::Groups => "groups",
+            Target::Email { .. } => "emails",
+            Target::Phone { .. } => "phones",
+            Target::Address { .. } => "address",
+            Target::Social(_) => "socials",
+        })
+    }
+
+    /// Google headers such as `E-mail 1 - Value` or `Address 2 - City`.
+    fn detect_numbered(header: &str) -> Option<Self> {
+        let (kind, field) = header.split_once(" - ")?;
+        let (kind, slot) = kind.rsplit_once(' ')?;
+        slot.parse::<u32>().ok()?;
+        let slot = slot.to_string();
+        let label = matches!(field, "Label" | "Type");
+
+        match (kind, field) {
+            ("E-mail", "Value") | ("E-mail", "Label") | ("E-mail", "Type") => Some(Target::Email { slot, label }),
+            ("Phone", "Value") | ("Phone", "Label") | ("Phone", "Type") => Some(Target::Phone { slot, label }),
+            ("Address", field) => {
+                let part = match field {
+                    "Label" | "Type" => AddressPart::Label,
+                    "Street" => AddressPart::Street,
+                    "City" => AddressPart::City,
+                    "Region" => AddressPart::Region,
+                    "Postal Code" => AddressPart::PostCode,
+                    "Country" => AddressPart::Country,
+                    _ => return None,
+                };
+                Some(Target::Address { slot, part })
+            },
+            _ => None,
+        }
+    }
+
+    /// Outlook headers such as `Home Street` or `Business Country/Region`.
+    fn detect_outlook_address(header: &str) -> Option<Self> {
+        let (slot, field) = header.split_once(' ')?;
+        let slot = match slot {
+            "Home" => "home",
+            "Business" => "work",
+            "Other" => "other",
+            _ => return None,
+        };
+        let part = match field {
+            "Street" => AddressPart::Street,
+            "City" => AddressPart::City,
+            "State" => AddressPart::Region,
+            "Postal Code" => AddressPart::PostCode,
+            "Country/Region" | "Country" => AddressPart::Country,
+            _ => return None,
+        };
+        Some(Target::Address { slot: slot.to_string(), part })
+    }
+}
+
+/// Which column holds which field, in column order.
+pub struct Mapping {
+    columns: Vec<(String, Target)>,
+}
+
+impl Mapping {
+    /// Read a mapping file: one `Header = target` line per column, where
+    /// `#` starts a comment. See `Target::parse` for the targets.
+    pub fn load(path: &str) -> Result<Self, TuppError> {
+        let text = fs::read_to_string(path).map_err(TuppError::Io)?;
+        let mut columns = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (header, target) = line.rsplit_once('=').ok_or_else(|| {
+                TuppError::Validation(format!("Line {} of {}: expected 'Header = target'", number + 1, path))
+            })?;
+            columns.push((header.trim().to_string(), Target::parse(target)?));
+        }
+        Ok(Self { columns })
+    }
+
+    fn target(&self, header: &str) -> Option<Target> {
+        self.columns
+            .iter()
+            .find(|(h, _)| h.eq_ignore_ascii_case(header.trim()))
+            .map(|(_, target)| target.clone())
+    }
+}
+
+/// Reads CSV files, with a mapping file or known headers.
+pub struct CsvImporter {
+    pub mapping: Option<Mapping>,
+}
+
+impl Importer for CsvImporter {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn read(&self, path: &Path, default_country_code: u16) -> Result<Fragment, TuppError> {
+        read(&read_text(path)?, self.mapping.as_ref(), default_country_code)
+    }
+}
+
+/// Read contacts from CSV, mapping columns with `mapping`, or by detecting
+/// known headers. Contacts are keyed by their `id` column, if there is one.
+pub fn read(text: &str, mapping: Option<&Mapping>, default_country_code: u16) -> Result<Fragment, TuppError> {
+    let mut records = parse_records(text).into_iter();
+    let headers = records.next().ok_or_else(|| TuppError::Validation("The CSV file is empty".to_string()))?;
+    let targets: Vec<Target> = headers
+        .iter()
+        .map(|h| match mapping {
+            Some(mapping) => mapping.target(h),
+            None => Target::detect(h),
+        }.unwrap_or(Target::Ignore))
+        .collect();
+
+    if targets.iter().all(|t| *t == Target::Ignore) {
+        return Err(TuppError::Validation(
+            "No column could be mapped to a contact field, please provide a mapping file with --mapping".to_string(),
+        ));
+    }
+
+    let mut fragment = Fragment::default();
+    for record in records {
+        let mut source_uid = None;
+        for ((header, target), value) in headers.iter().zip(&targets).zip(&record) {
+            if value.trim().is_empty() {
+                continue;
+            }
+            fragment.report.record(header, target.field());
+            if *target == Target::Id {
+                source_uid = Some(value.trim().to_string());
+            }
+        }
+        let mut imported = read_record(&targets, &record, default_country_code);
+        imported.source_uid = source_uid;
+        fragment.contacts.push(imported);
+    }
+    Ok(fragment)
+}
+
+/// Values of an email or phone slot, with the label column if there is one.
+#[derive(Default)]
+struct Slot {
+    name: String,
+    label: Option<String>,
+    value: String,
+}
+
+fn read_record(targets: &[Target], record: &[String], default_country_code: u16) -> Imported {
+    let mut identity = Identity::default();
+    let mut identifier = None;
+    let mut full_name = None;
+    let mut groups = Vec::new();
+    let mut emails: Vec<Slot> = Vec::new();
+    let mut phones: Vec<Slot> = Vec::new();
+    let mut addresses: Vec<(String, Address)> = Vec::new();
+    let mut socials = Vec::new();
+    let mut unmapped = Vec::new();
+
+    for (target, value) in targets.iter().zip(record) {
+        let value = value.trim();
+        let text = Some(value.to_string()).filter(|v| !v.is_empty());
+        match target {
+            Target::Ignore => {},
+            Target::Id => identifier = Uuid::parse_str(value).ok(),
+            Target::Name => full_name = text,
+            Target::Title => identity.title = text,
+            Target::First => identity.first_name = text,
+            Target::Middle => identity.middle_name = text,
+            Target::Last => identity.last_name = text,
+            Target::Post => identity.post_nominal = text,
+            Target::Gender => identity.gender = match value.to_lowercase().as_str() {
+                "m" | "male" => Some(Gender::Male),
+                "f" | "female" => Some(Gender::Female),
+                "o" | "other" | "non-binary" | "nonbinary" => Some(Gender::NonBinary),
+                _ => None,
+            },
+            Target::Birth | Target::Anniversary | Target::Death if !value.is_empty() => match parse_date(value) {
+                Some(date) => match target {
+                    Target::Birth => identity.birth_date = Some(date),
+                    Target::Anniversary => identity.anniversary = Some(date),
+                    _ => {
+                        identity.death_date = Some(date);
+                        identity.is_alive = false;
+                    },
+                },
+                None => unmapped.push(format!("date '{}'", value)),
+            },
+            Target::Birth | Target::Anniversary | Target::Death => {},
+            Target::Groups => {
+                for group in split_list(value) {
+                    // Google system labels, such as "* myContacts"
+                    if !group.starts_with('*') && !groups.contains(&group) {
+                        groups.push(group);
+                    }
+                }
+            },
+            Target::Email { slot, label } => fill_slot(&mut emails, slot, *label, value),
+            Target::Phone { slot, label } => fill_slot(&mut phones, slot, *label, value),
+            Target::Address { slot, part } => {
+                let index = match addresses.iter().position(|(s, _)| s == slot) {
+                    Some(index) => index,
+                    None => {
+                        addresses.push((slot.clone(), empty_address()));
+                        addresses.len() - 1
+                    },
+                };
+                let address = &mut addresses[index].1;
+                match part {
+                    AddressPart::Label => address.label = text.map(|l| clean_label(&l)),
+                    AddressPart::Number => address.number = text,
+                    AddressPart::Street => address.street = text,
+                    AddressPart::City => address.city = text,
+                    AddressPart::Region => address.region = text,
+                    AddressPart::PostCode => address.post_code = text,
+                    AddressPart::Country => address.country = text,
+                }
+            },
+            Target::Social(network) => {
+                if let Some(username) = text {
+                    socials.push(Social { label: None, network: network.clone(), username: Some(username) });
+                }
+            },
+        }
+    }
+
+    if identity.first_name.is_none() && identity.last_name.is_none() {
+        if let Some(full_name) = full_name {
+            let mut words = full_name.split_whitespace();
+            identity.first_name = words.next().map(str::to_string);
+            identity.last_name = Some(words.collect::<Vec<_>>().join(" ")).filter(|r| !r.is_empty());
+        }
+    }
+
+    let mut contact_emails: Vec<Email> = Vec::new();
+    for slot in emails {
+        for address in split_list(&slot.value) {
+            let label = unique_label(Some(slot_label(&slot)), contact_emails.iter().map(|e| e.label.as_deref()));
+            contact_emails.push(Email { label, address: Some(address) });
+        }
+    }
+
+    let mut contact_phones: Vec<PhoneNumber> = Vec::new();
+    for slot in phones {
+        for number in split_list(&slot.value) {
+            let label = unique_label(Some(slot_label(&slot)), contact_phones.iter().map(|p| p.label.as_deref()));
+            match PhoneNumber::parse(label, &number, default_country_code) {
+                Some(phone) => contact_phones.push(phone),
+                None => unmapped.push(format!("phone '{}'", number)),
+            }
+        }
+    }
+
+    // A contact has a single address: the first one with a value
+    let mut addresses = addresses.into_iter().filter(|(_, a)| has_value(a));
+    let address = addresses.next().map(|(slot, mut address)| {
+        if address.number.is_none() {
+            (address.number, address.street) = super::split_street(address.street.take());
+        }
+        if address.label.is_none() {
+            address.label = Some(if slot.parse::<u32>().is_ok() { "default".to_string() } else { slot });
+        }
+        address
+    });
+    unmapped.extend(addresses.map(|(slot, _)| format!("address {} (only one address is kept)", slot)));
+
+    let mut contact = Contact::new(identity);
+    if let Some(id) = identifier {
+        contact.identifier = id;
+    }
+    contact.address = address;
+    contact.emails = Some(contact_emails).filter(|v| !v.is_empty());
+    contact.phones = Some(contact_phones).filter(|v| !v.is_empty());
+    contact.socials = Some(socials).filter(|v| !v.is_empty());
+
+    Imported::new(contact, groups, unmapped)
+}
+
+fn fill_slot(slots: &mut Vec<Slot>, name: &str, label: bool, value: &str) {
+    let index = match slots.iter().position(|s| s.name == name) {
+        Some(index) => index,
+        None => {
+            slots.push(Slot { name: name.to_string(), ..Default::default() });
+            slots.len() - 1
+        },
+    };
+    let value = value.trim();
+    if label {
+        slots[index].label = Some(clean_label(value)).filter(|l| !l.is_empty());
+    } else {
+        slots[index].value = value.to_string();
+    }
+}
+
+/// The label of a slot: its label column, or its name unless it is a number.
+fn slot_label(slot: &Slot) -> String {
+    match &slot.label {
+        Some(label) => label.clone(),
+        None if slot.name.parse::<u32>().is_err() => slot.name.clone(),
+        None => "default".to_string(),
+    }
+}
+
+/// Google writes labels like "* Work".
+fn clean_label(label: &str) -> String {
+    label.trim_start_matches('*').trim().to_lowercase()
+}
+
+/// Split a cell holding several values, as Google (`a ::: b`) and Outlook (`a;b`) do.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(":::")
+        .flat_map(|part| part.split(';'))
+        .map(|part| part.trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn empty_address() -> Address {
+    Address { label: None, country: None, region: None, city: None, post_code: None, street: None, number: None }
+}
+
+fn has_value(address: &Address) -> bool {
+    [&address.number, &address.street, &address.city, &address.region, &address.post_code, &address.country]
+        .iter()
+        .any(|part| part.is_some())
+}
+
+/// Read dates as written by tupp (`1990-6-18`), Google (`1990-06-18`,
+/// `--06-18`) and Outlook (`6/18/1990`).
+fn parse_date(value: &str) -> Option<Date> {
+    if let Ok(date) = value.parse::<Date>() {
+        return Some(date);
+    }
+    if let Some(date) = super::vcard::parse_date(value) {
+        return Some(date);
+    }
+    let parts: Vec<&str> = value.split('/').collect();
+    if let [month, day, year] = parts.as_slice() {
+        let year: i32 = year.parse().ok()?;
+        if year == 0 {
+            return None;
+        }
+        return format!("{}-{}-{}", year, month, day).parse().ok();
+    }
+    None
+}
+
+/// Split CSV text into records, following RFC 4180: fields can be quoted,
+/// and quoted fields can hold separators, line breaks and doubled quotes.
+/// The separator is guessed from the header line.
+fn parse_records(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let header = text.lines().next().unwrap_or_default();
+    let separator = [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|s| header.matches(*s).count())
+        .unwrap_or(',');
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                },
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '\r' => {},
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            },
+            c if c == separator => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records.retain(|r| r.iter().any(|field| !field.is_empty()));
+    records
+}
+
+/// Write contacts as CSV, with the columns of `mapping`, or by default with
+/// Google Contacts headers, so that the file can be imported back.
+pub fn write(contacts: &[&Contact], data: &TuppData, mapping: Option<&Mapping>, name: &NameFormat) -> Result<String, TuppError> {
+    let columns = match mapping {
+        Some(mapping) => mapping.columns.clone(),
+        None => default_columns(contacts)?,
+    };
+
+    let mut out = String::new();
+    let line = |cells: Vec<String>| cells.iter().map(|c| csv_escape(c)).collect::<Vec<_>>().join(",") + "\r\n";
+    out.push_str(&line(columns.iter().map(|(header, _)| header.clone()).collect()));
+    for contact in contacts {
+        out.push_str(&line(columns.iter().map(|(_, target)| cell(contact, data, target, &columns, name)).collect()));
+    }
+    Ok(out)
+}
+
+fn default_columns(contacts: &[&Contact]) -> Result<Vec<(String, Target)>, TuppError> {
+    let mut columns: Vec<(String, &str)> = [
+        ("ID", "id"),
+        ("Name Prefix", "title"),
+        ("First Name", "first"),
+        ("Middle Name", "middle"),
+        ("Last Name", "last"),
+        ("Name Suffix", "post"),
+        ("Gender", "gender"),
+        ("Birthday", "birth"),
+        ("Anniversary", "anniversary"),
+        ("Death Date", "death"),
+    ]
+    .into_iter()
+    .map(|(header, target)| (header.to_string(), target))
+    .collect();
+    let mut targets: Vec<String> = Vec::new();
+
+    let emails = contacts.iter().map(|c| c.emails.as_ref().map_or(0, Vec::len)).max().unwrap_or(0);
+    for i in 1..=emails {
+        columns.push((format!("E-mail {} - Label", i), ""));
+        targets.push(format!("email.{}.label", i));
+        columns.push((format!("E-mail {} - Value", i), ""));
+        targets.push(format!("email.{}", i));
+    }
+    let phones = contacts.iter().map(|c| c.phones.as_ref().map_or(0, Vec::len)).max().unwrap_or(0);
+    for i in 1..=phones {
+        columns.push((format!("Phone {} - Label", i), ""));
+        targets.push(format!("phone.{}.label", i));
+        columns.push((format!("Phone {} - Value", i), ""));
+        targets.push(format!("phone.{}", i));
+    }
+    if contacts.iter().any(|c| c.address.is_some()) {
+        for (field, part) in [("Label", "label"), ("Street", "street"), ("City", "city"), ("Region", "region"), ("Postal Code", "post_code"), ("Country", "country")] {
+            columns.push((format!("Address 1 - {}", field), ""));
+            targets.push(format!("address.1.{}", part));
+        }
+    }
+    let mut networks: Vec<String> = contacts.iter()
+        .flat_map(|c| c.socials.iter().flatten())
+        .map(|s| s.network.to_lowercase())
+        .collect();
+    networks.sort();
+    networks.dedup();
+    for network in networks {
+        columns.push((format!("social.{}", network), ""));
+        targets.push(format!("social.{}", network));
+    }
+    columns.push(("Labels".to_string(), "groups"));
+
+    let mut targets = targets.into_iter();
+    columns
+        .into_iter()
+        .map(|(header, target)| {
+            let target = if target.is_empty() { targets.next().unwrap_or_default() } else { target.to_string() };
+            Ok((header, Target::parse(&target)?))
+        })
+        .collect()
+}
+
+fn cell(contact: &Contact, data: &TuppData, target: &Target, columns: &[(String, Target)], name: &NameFormat) -> String {
+    let identity = &contact.identity;
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let date = |value: &Option<Date>| value.as_ref().map(iso_date).unwrap_or_default();
+
+    match target {
+        Target::Ignore => String::new(),
+        Target::Id => contact.identifier.to_string(),
+        Target::Name => contact.format_name(name),
+        Target::Title => text(&identity.title),
+        Target::First => text(&identity.first_name),
+        Target::Middle => text(&identity.middle_name),
+        Target::Last => text(&identity.last_name),
+        Target::Post => text(&identity.post_nominal),
+        Target::Gender => match identity.gender {
+            Some(Gender::Male) => "male",
+            Some(Gender::Female) => "female",
+            Some(Gender::NonBinary) => "non-binary",
+            None => "",
+        }.to_string(),
+        Target::Birth => date(&identity.birth_date),
+        Target::Anniversary => date(&identity.anniversary),
+        Target::Death => date(&identity.death_date),
+        Target::Groups => {
+            let mut names: Vec<String> = contact.groups.iter().flatten()
+                .filter_map(|id| Group::find_group_by_id_recursive(&data.groups, id))
+                .map(|g| g.name.clone())
+                .collect();
+            names.sort();
+            names.join(" ::: ")
+        },
+        Target::Email { slot, label } => {
+            let emails: Vec<&Email> = contact.emails.iter().flatten().collect();
+            match find_slot(&emails, slot, |e| e.label.as_deref()) {
+                Some(email) if *label => text(&email.label),
+                Some(email) => text(&email.address),
+                None => String::new(),
+            }
+        },
+        Target::Phone { slot, label } => {
+            let phones: Vec<&PhoneNumber> = contact.phones.iter().flatten().collect();
+            match find_slot(&phones, slot, |p| p.label.as_deref()) {
+                Some(phone) if *label => text(&phone.label),
+                Some(phone) => format!("+{}{}", phone.country_code, phone.number),
+                None => String::new(),
+            }
+        },
+        Target::Address { slot, part } => {
+            let addresses: Vec<&Address> = contact.address.iter().collect();
+            match find_slot(&addresses, slot, |a| a.label.as_deref()) {
+                Some(address) => match part {
+                    AddressPart::Label => text(&address.label),
+                    AddressPart::Number => text(&address.number),
+                    // Without a number column, the number goes with the street
+                    AddressPart::Street if !has_number_column(columns, slot) => [&address.number, &address.street]
+                        .into_iter()
+                        .flatten()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    AddressPart::Street => text(&address.street),
+                    AddressPart::City => text(&address.city),
+                    AddressPart::Region => text(&address.region),
+                    AddressPart::PostCode => text(&address.post_code),
+                    AddressPart::Country => text(&address.country),
+                },
+                None => String::new(),
+            }
+        },
+        Target::Social(network) => contact.socials.iter().flatten()
+            .find(|s| s.network.eq_ignore_ascii_case(network))
+            .and_then(|s| s.username.clone())
+            .unwrap_or_default(),
+    }
+}
+
+/// Numbered slots pick items by position (from 1), others by label.
+fn find_slot<'a, T>(items: &[&'a T], slot: &str, label: impl Fn(&T) -> Option<&str>) -> Option<&'a T> {
+    match slot.parse::<usize>() {
+        Ok(position) => items.get(position.checked_sub(1)?).copied(),
+        Err(_) => items.iter().find(|item| label(item).is_some_and(|l| l.eq_ignore_ascii_case(slot))).copied(),
+    }
+}
+
+fn has_number_column(columns: &[(String, Target)], slot: &str) -> bool {
+    columns.iter().any(|(_, target)| {
+        matches!(target, Target::Address { slot: s, part: AddressPart::Number } if s == slot)
+    })
+}
+
+fn iso_date(date: &Date) -> String {
+    match (date.year, date.month, date.day) {
+        (Some(y), Some(m), Some(d)) => format!("{:04}-{:02}-{:02}", y, m, d),
+        (None, Some(m), Some(d)) => format!("--{:02}-{:02}", m, d),
+        _ => super::vcard::format_date(date).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use crate::contact::Contact;
+use crate::models::Date;
+use crate::name_format::FIRST_LAST;
+use super::vcard::{escape, fold_line};
+
+/// The year of dates whose year is unknown. It is a leap year, so that
+/// February 29 exists, and the one Apple Calendar uses for the same purpose.
+const UNKNOWN_YEAR: i32 = 1604;
+
+/// A yearly event of a contact.
+struct Event<'a> {
+    kind: &'static str,
+    summary: String,
+    /// What the year of the date means, as in "Born in {}".
+    year_note: &'static str,
+    date: &'a Date,
+}
+
+/// Write the birthdays, anniversaries and death anniversaries of contacts as
+/// an iCalendar file (RFC 5545) of yearly, all-day events. Dates without a
+/// day or a month are left out; dates without a year start in 1604.
+pub fn write(contacts: &[&Contact]) -> String {
+    let mut out = String::new();
+    for line in ["BEGIN:VCALENDAR", "VERSION:2.0", "PRODID:-//tupp//Contacts//EN", "CALSCALE:GREGORIAN", "X-WR-CALNAME:Contacts"] {
+        fold_line(line, &mut out);
+    }
+
+    for contact in contacts {
+        let stamp = contact.updated.or(contact.created).unwrap_or_else(Utc::now);
+        for event in events(contact) {
+            write_event(contact, &event, &stamp, &mut out);
+        }
+    }
+
+    fold_line("END:VCALENDAR", &mut out);
+    out
+}
+
+fn events(contact: &Contact) -> Vec<Event<'_>> {
+    let identity = &contact.identity;
+    let name = contact.format_name(&FIRST_LAST);
+    let dates = [
+        ("birthday", format!("Birthday of {}", name), "Born in {}", &identity.birth_date),
+        ("anniversary", format!("Anniversary of {}", name), "Since {}", &identity.anniversary),
+        ("death", format!("Death anniversary of {}", name), "Died in {}", &identity.death_date),
+    ];
+    dates
+        .into_iter()
+        .filter_map(|(kind, summary, year_note, date)| {
+            let date = date.as_ref().filter(|d| d.month.is_some() && d.day.is_some())?;
+            Some(Event { kind, summary, year_note, date })
+        })
+        .collect()
+}
+
+fn write_event(contact: &Contact, event: &Event, stamp: &DateTime<Utc>, out: &mut String) {
+    let (month, day) = (event.date.month.unwrap_or(1), event.date.day.unwrap_or(1));
+    let year = event.date.year.unwrap_or(UNKNOWN_YEAR);
+
+    // February 29 falls on the last day of February in other years
+    let rule = if (month, day) == (2, 29) {
+        "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1"
+    } else {
+        "FREQ=YEARLY"
+    };
+    let start = if event.date.year.is_some() {
+        format!("DTSTART;VALUE=DATE:{:04}{:02}{:02}", year, month, day)
+    } else {
+        format!("DTSTART;VALUE=DATE;X-APPLE-OMIT-YEAR={}:{:04}{:02}{:02}", UNKNOWN_YEAR, year, month, day)
+    };
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}-{}@tupp", contact.identifier, event.kind),
+        format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+        start,
+        format!("RRULE:{}", rule),
+        format!("SUMMARY:{}", escape(&event.summary)),
+    ];
+    if let Some(year) = event.date.year {
+        lines.push(format!("DESCRIPTION:{}", escape(&event.year_note.replace("{}", &year.to_string()))));
+    }
+    lines.extend([
+        format!("CATEGORIES:{}", event.kind.to_uppercase()),
+        "TRANSP:TRANSPARENT".to_string(),
+        "END:VEVENT".to_string(),
+    ]);
+
+    for line in lines {
+        fold_line(&line, out);
+    }
+}
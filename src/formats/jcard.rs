@@ -0,0 +1,231 @@
+use serde_json::{json, Map, Value};
+use crate::contact::Contact;
+use crate::models::TuppData;
+use crate::name_format::NameFormat;
+use super::vcard::{self, Property};
+
+/// Write contacts as an array of jCards (RFC 7095), the JSON form of the
+/// vCard 4.0 export.
+pub fn write(contacts: &[&Contact], data: &TuppData, name: &NameFormat) -> Value {
+    Value::Array(contacts.iter().map(|contact| to_jcard(contact, data, name)).collect())
+}
+
+pub fn to_jcard(contact: &Contact, data: &TuppData, name: &NameFormat) -> Value {
+    let properties: Vec<Value> = vcard::to_properties(contact, data, name).iter().map(property).collect();
+    json!(["vcard", properties])
+}
+
+/// `[name, parameters, type, value...]`, where structured values are arrays
+/// and lists are given as several values.
+fn property(property: &Property) -> Value {
+    let mut params = Map::new();
+    for (name, _) in &property.params {
+        if name == "VALUE" || params.contains_key(&name.to_lowercase()) {
+            continue;
+        }
+        let values: Vec<&str> = property.param(name).collect();
+        let value = match values.as_slice() {
+            [value] => json!(value),
+            values => json!(values),
+        };
+        params.insert(name.to_lowercase(), value);
+    }
+
+    let value_type = property.value_type();
+    let mut item = vec![json!(property.name.to_lowercase()), Value::Object(params), json!(value_type)];
+    if property.is_structured() {
+        item.push(json!(property.components()));
+    } else if property.is_list() {
+        item.extend(property.list().into_iter().map(Value::String));
+    } else {
+        let text = property.text();
+        item.push(json!(match value_type.as_str() {
+            "date" => extended_date(&text),
+            "timestamp" => extended_timestamp(&text),
+            _ => text,
+        }));
+    }
+    Value::Array(item)
+}
+
+/// jCard dates use the extended format: `1990-06-18`, `--06-18`, `---18`.
+fn extended_date(value: &str) -> String {
+    let date = match vcard::parse_date(value) {
+        Some(date) => date,
+        None => return value.to_string(),
+    };
+    let part = |n: Option<u8>| n.map(|n| format!("{:02}", n));
+    match (date.year, part(date.month), part(date.day)) {
+        (Some(y), Some(m), Some(d)) => format!("{:04}-{}-{}", y, m, d),
+        (Some(y), Some(m), None) => format!("{:04}-{}", y, m),
+        (Some(y), None, _) => format!("{:04}", y),
+        (None, Some(m), Some(d)) => format!("--{}-{}", m, d),
+        (None, Some(m), None) => format!("--{}", m),
+        (None, None, Some(d)) => format!("---{}", d),
+        (None, None, None) => String::new(),
+    }
+}
+
+/// `20240118T093000Z` becomes `2024-01-18T09:30:00Z`.
+fn extended_timestamp(value: &str) -> String {
+    match chrono::NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S") {
+        Ok(time) => time.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashSet;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use super::to_jcard;
+    use crate::commands::serve::negotiate;
+    use crate::contact::{Contact, Link, Relation};
+    use crate::formats::vcard::{self, Property};
+    use crate::formats::ExportFormat;
+    use crate::name_format::FULL_NAME;
+    use crate::group::Group;
+    use crate::models::{Address, Email, Gender, Identity, PhoneNumber, TuppData};
+
+    /// A contact with every field the vCard export writes, in a group and
+    /// linked to another contact.
+    pub(crate) fn sample_data() -> TuppData {
+        let family = Group::new("Family".to_string());
+        let spouse = Contact::new(Identity {
+            first_name: Some("Marie".to_string()),
+            last_name: Some("Curie".to_string()),
+            ..Identity::default()
+        });
+
+        let mut contact = Contact::new(Identity {
+            title: Some("Dr".to_string()),
+            first_name: Some("Pierre".to_string()),
+            middle_name: Some("Paul".to_string()),
+            last_name: Some("Curie".to_string()),
+            post_nominal: Some("PhD".to_string()),
+            gender: Some(Gender::Male),
+            birth_date: Some("1859-05-15".parse().unwrap()),
+            anniversary: Some("1895-07-26".parse().unwrap()),
+            ..Identity::default()
+        });
+        contact.emails = Some(vec![
+            Email { label: Some("home".to_string()), address: Some("pierre@example.org".to_string()) },
+            Email { label: Some("work".to_string()), address: Some("p.curie@sorbonne.example".to_string()) },
+        ]);
+        contact.phones = Some(vec![
+            PhoneNumber { label: Some("home".to_string()), country_code: 33, number: 612345678 },
+            PhoneNumber { label: Some("work".to_string()), country_code: 44, number: 2079460000 },
+        ]);
+        contact.address = Some(Address {
+            label: Some("home".to_string()),
+            country: Some("France".to_string()),
+            region: Some("Île-de-France".to_string()),
+            city: Some("Paris".to_string()),
+            post_code: Some("75005".to_string()),
+            street: Some("Rue Cuvier; Bâtiment B, 2e".to_string()),
+            number: Some("12".to_string()),
+        });
+        contact.groups = Some(HashSet::from([family.identifier]));
+        contact.links = Some(vec![Link { target: spouse.identifier, relation: Relation::Spouse }]);
+
+        TuppData { contacts: vec![contact, spouse], groups: vec![family] }
+    }
+
+    /// Read properties parsed back from an export as a contact, and check
+    /// that it matches the first contact of `sample_data`.
+    pub(crate) fn assert_round_trip(properties: &[Property], data: &TuppData) {
+        let original = &data.contacts[0];
+        let imported = vcard::to_contact(properties, 33);
+        let contact = &imported.contact;
+
+        assert_eq!(contact.identifier, original.identifier);
+        assert_eq!(json(&contact.identity), json(&original.identity));
+        assert_eq!(json(&contact.emails), json(&original.emails));
+        assert_eq!(json(&contact.phones), json(&original.phones));
+        assert_eq!(json(&contact.address), json(&original.address));
+        assert_eq!(imported.groups, vec!["Family".to_string()]);
+
+        // Links are exported but not imported, as their target may be missing
+        assert_eq!(imported.unmapped, vec!["RELATED".to_string()]);
+        let related: Vec<&Property> = properties.iter().filter(|p| p.name == "RELATED").collect();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].text(), format!("urn:uuid:{}", data.contacts[1].identifier));
+        assert_eq!(related[0].param("TYPE").collect::<Vec<_>>(), vec!["spouse"]);
+    }
+
+    /// Models are compared as JSON, as they do not implement `PartialEq`.
+    fn json(value: &impl Serialize) -> Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    /// Parse a jCard back into vCard properties with escaped values.
+    fn parse(jcard: &Value) -> Vec<Property> {
+        assert_eq!(jcard[0], "vcard");
+        jcard[1].as_array().unwrap().iter().map(|item| {
+            let item = item.as_array().unwrap();
+            let mut params = Vec::new();
+            for (name, value) in item[1].as_object().unwrap() {
+                let values = match value {
+                    Value::Array(values) => values.clone(),
+                    value => vec![value.clone()],
+                };
+                for value in values {
+                    params.push((name.to_uppercase(), value.as_str().unwrap().to_string()));
+                }
+            }
+
+            let text = |value: &Value| vcard::escape(value.as_str().unwrap());
+            let value = match &item[3..] {
+                [Value::Array(components)] => components.iter().map(text).collect::<Vec<_>>().join(";"),
+                values => values.iter().map(text).collect::<Vec<_>>().join(","),
+            };
+            Property { name: item[0].as_str().unwrap().to_uppercase(), params, value }
+        }).collect()
+    }
+
+    #[test]
+    fn jcard_round_trip() {
+        let data = sample_data();
+        let jcard = to_jcard(&data.contacts[0], &data, &FULL_NAME);
+        let properties = parse(&jcard);
+
+        let names: Vec<&str> = properties.iter().map(|p| p.name.as_str()).collect();
+        let expected: Vec<String> = vcard::to_properties(&data.contacts[0], &data, &FULL_NAME).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, expected);
+        assert_round_trip(&properties, &data);
+    }
+
+    #[test]
+    fn jcard_dates_use_the_extended_format() {
+        let data = sample_data();
+        let jcard = to_jcard(&data.contacts[0], &data, &FULL_NAME);
+        let bday = jcard[1].as_array().unwrap().iter().find(|p| p[0] == "bday").unwrap();
+        assert_eq!(bday[2], "date");
+        assert_eq!(bday[3], json!("1859-05-15"));
+    }
+
+    #[test]
+    fn negotiate_picks_jcard() {
+        let format = |accept| negotiate(Some(accept)).map(|(format, _)| format);
+        assert_eq!(format("application/vcard+json"), Some(ExportFormat::Jcard));
+        assert_eq!(format("text/html, application/vcard+json;q=0.9"), Some(ExportFormat::Jcard));
+        assert_eq!(negotiate(Some("application/vcard+json")).map(|(_, media)| media), Some("application/vcard+json"));
+        assert_eq!(negotiate(None), None);
+    }
+
+    #[test]
+    fn negotiate_follows_q_values() {
+        let format = |accept| negotiate(Some(accept)).map(|(format, _)| format);
+        // JSON is preferred, so tupp JSON is returned
+        assert_eq!(format("application/json, text/vcard;q=0.1"), None);
+        assert_eq!(format("text/vcard;q=0.1, */*"), None);
+        assert_eq!(format("text/vcard;q=0"), None);
+        assert_eq!(format("text/vcard;q=0, application/vcard+xml;q=0.2"), Some(ExportFormat::Xcard));
+        assert_eq!(format("text/vcard;q=0.5, application/vcard+json;q=0.9"), Some(ExportFormat::Jcard));
+        assert_eq!(format("application/json;q=0.4, text/vcard;q=0.8"), Some(ExportFormat::Vcard));
+        // Ties go to the first listed, and unknown types are ignored
+        assert_eq!(format("text/html, text/vcard, application/vcard+json"), Some(ExportFormat::Vcard));
+        assert_eq!(format("text/vcard; Q=0.3, application/json; q=0.3"), Some(ExportFormat::Vcard));
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+use crate::import::{read_text, Fragment, Importer};
+use crate::error::TuppError;
+
+/// Reads the vCard directories of khard (and vdirsyncer): a file per
+/// contact, with a subdirectory per address book.
+pub struct KhardImporter;
+
+impl Importer for KhardImporter {
+    fn name(&self) -> &'static str {
+        "khard"
+    }
+
+    /// Read every `.vcf` file under `path`. The contacts of an address book
+    /// are put in a group named after it; contacts are keyed by their `UID`,
+    /// or else by their file name, which khard makes from the `UID`.
+    fn read(&self, path: &Path, default_country_code: u16) -> Result<Fragment, TuppError> {
+        if !path.is_dir() {
+            return Err(TuppError::Validation(format!("{} is not a khard address book directory", path.display())));
+        }
+
+        let mut fragment = Fragment::default();
+        for file in vcf_files(path)? {
+            let mut cards = super::vcard::read(&read_text(&file)?, default_country_code)?;
+            let book = file.parent()
+                .filter(|parent| *parent != path)
+                .and_then(|parent| parent.file_name())
+                .map(|name| name.to_string_lossy().into_owned());
+            let stem = file.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+
+            for imported in &mut cards.contacts {
+                imported.groups.extend(book.clone());
+                if imported.source_uid.is_none() && stem.is_some() {
+                    imported.source_uid = stem.clone();
+                }
+            }
+            fragment.contacts.append(&mut cards.contacts);
+            fragment.report.extend(cards.report);
+        }
+        Ok(fragment)
+    }
+}
+
+/// The `.vcf` files under a directory, at any depth, in name order.
+pub fn vcf_files(dir: &Path) -> Result<Vec<PathBuf>, TuppError> {
+    let mut files = Vec::new();
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(vcf_files(&entry)?);
+        } else if entry.extension().is_some_and(|e| e.eq_ignore_ascii_case("vcf")) {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
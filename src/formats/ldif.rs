@@ -0,0 +1,433 @@
+use std::collections::HashSet;
+use std::path::Path;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use uuid::Uuid;
+use crate::contact::Contact;
+use crate::import::{read_text, Fragment, Imported, Importer};
+use crate::merge::unique_label;
+use crate::name_format::NameFormat;
+use crate::models::{Address, Email, Identity, PhoneNumber};
+use crate::error::TuppError;
+
+/// Object classes of exported entries.
+const OBJECT_CLASSES: &[&str] = &["top", "person", "organizationalPerson", "inetOrgPerson"];
+
+/// Object classes of the entries that are read as contacts.
+const PERSON_CLASSES: &[&str] = &["person", "organizationalperson", "inetorgperson"];
+
+/// Attributes that need no mapping, or whose value is used elsewhere.
+const STRUCTURAL: &[&str] = &["dn", "changetype", "objectclass", "cn", "sn", "givenname", "uid", "entryuuid", "street", "l", "st", "postalcode", "postaladdress"];
+
+/// The contact field of each imported attribute, for the mapping report.
+const TARGETS: &[(&str, &str)] = &[
+    ("cn", "name"), ("sn", "name"), ("givenname", "name"), ("mail", "emails"), ("telephonenumber", "phones"),
+    ("mobile", "phones"), ("homephone", "phones"), ("street", "address"), ("l", "address"), ("st", "address"),
+    ("postalcode", "address"), ("postaladdress", "address"),
+    ("uid", "identifier"), ("entryuuid", "identifier"), ("createtimestamp", "created"), ("modifytimestamp", "updated"),
+];
+
+/// Labels of the phone attributes of inetOrgPerson.
+const PHONE_ATTRIBUTES: &[(&str, &str)] = &[("telephonenumber", "work"), ("mobile", "mobile"), ("homephone", "home")];
+
+/// Lines are folded after this many bytes, as in most LDAP tools.
+const LINE_LENGTH: usize = 76;
+
+/// An attribute of an entry, named in lowercase and without options
+/// (`cn;lang-fr` is `cn`), with its decoded value.
+pub struct Attribute {
+    pub name: String,
+    pub value: String,
+}
+
+/// Split an LDIF file (RFC 2849) into the attributes of each entry. Entries
+/// are separated by blank lines; values may be folded and base64 encoded.
+pub fn parse(text: &str) -> Result<Vec<Vec<Attribute>>, TuppError> {
+    let mut entries = Vec::new();
+    let mut entry = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+
+    // Unfold: a line starting with a space continues the previous one
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix(' '), lines.last_mut()) {
+            (Some(rest), Some(last)) if !last.is_empty() => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    for (number, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            if !entry.is_empty() {
+                entries.push(std::mem::take(&mut entry));
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| TuppError::Validation(format!("Line {}: expected 'attribute: value'", number + 1)))?;
+        let name = name.split(';').next().unwrap_or_default().trim().to_lowercase();
+        let value = if let Some(encoded) = value.strip_prefix(':') {
+            let bytes = STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| TuppError::Validation(format!("Line {}: invalid base64 value ({})", number + 1, e)))?;
+            String::from_utf8_lossy(&bytes).into_owned()
+        } else if value.starts_with('<') {
+            return Err(TuppError::Validation(format!("Line {}: values read from URLs are not supported", number + 1)));
+        } else {
+            value.trim_start().to_string()
+        };
+
+        if name == "version" && entry.is_empty() {
+            continue;
+        }
+        entry.push(Attribute { name, value });
+    }
+    if !entry.is_empty() {
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Reads LDIF files.
+pub struct LdifImporter;
+
+impl Importer for LdifImporter {
+    fn name(&self) -> &'static str {
+        "ldif"
+    }
+
+    fn read(&self, path: &Path, default_country_code: u16) -> Result<Fragment, TuppError> {
+        read(&read_text(path)?, default_country_code)
+    }
+}
+
+/// Read the person entries of an LDIF file as contacts, keyed by their
+/// `entryUUID`, `uid` or DN. Other entries, such as organizational units,
+/// and change records other than additions are left out.
+pub fn read(text: &str, default_country_code: u16) -> Result<Fragment, TuppError> {
+    let mut fragment = Fragment::default();
+    for entry in parse(text)?.iter().filter(|entry| is_person(entry)) {
+        for attribute in entry.iter().filter(|a| !matches!(a.name.as_str(), "dn" | "changetype" | "objectclass")) {
+            let target = TARGETS.iter().find(|(name, _)| *name == attribute.name).map(|(_, target)| *target);
+            fragment.report.record(&attribute.name, target);
+        }
+
+        let mut imported = to_contact(entry, default_country_code);
+        imported.source_uid = ["entryuuid", "uid", "dn"]
+            .iter()
+            .find_map(|name| entry.iter().find(|a| a.name == *name))
+            .map(|a| a.value.trim().to_string())
+            .filter(|uid| !uid.is_empty());
+        fragment.contacts.push(imported);
+    }
+    Ok(fragment)
+}
+
+fn is_person(entry: &[Attribute]) -> bool {
+    let values = |name: &'static str| entry.iter().filter(move |a| a.name == name).map(|a| a.value.trim().to_lowercase());
+    let added = values("changetype").all(|change| change == "add");
+    let person = values("objectclass").any(|class| PERSON_CLASSES.contains(&class.as_str()));
+    added && person
+}
+
+/// Map the attributes of an inetOrgPerson entry onto a contact. Attributes
+/// with no equivalent are listed in `unmapped`.
+pub fn to_contact(entry: &[Attribute], default_country_code: u16) -> Imported {
+    let first = |name: &str| {
+        entry.iter()
+            .find(|a| a.name == name)
+            .map(|a| a.value.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    // The person class requires cn and sn, so exporters fill them with the
+    // full name or the uid when there is none: such values are no names
+    let uid = first("uid");
+    let given_name = first("givenname");
+    let full_name = first("cn").filter(|cn| uid.as_ref() != Some(cn));
+    let last_name = first("sn").filter(|sn| {
+        uid.as_ref() != Some(sn)
+            && given_name.as_ref() != Some(sn)
+            && !(given_name.is_some() && full_name.as_ref() == Some(sn))
+    });
+
+    let mut identity = Identity {
+        first_name: given_name,
+        last_name,
+        ..Default::default()
+    };
+    if identity.first_name.is_none() && identity.last_name != full_name {
+        if let Some(full_name) = full_name {
+            let mut words = full_name.split_whitespace();
+            identity.first_name = words.next().map(str::to_string);
+            if identity.last_name.is_none() {
+                identity.last_name = Some(words.collect::<Vec<_>>().join(" ")).filter(|r| !r.is_empty());
+            }
+        }
+    }
+
+    let mut emails: Vec<Email> = Vec::new();
+    let mut phones: Vec<PhoneNumber> = Vec::new();
+    let mut created = None;
+    let mut updated = None;
+    let mut unmapped = Vec::new();
+
+    for attribute in entry {
+        let value = attribute.value.trim();
+        match attribute.name.as_str() {
+            "mail" if !value.is_empty() => {
+                let label = unique_label(Some("default".to_string()), emails.iter().map(|e| e.label.as_deref()));
+                emails.push(Email { label, address: Some(value.to_string()) });
+            },
+            // Contacts have no organization, but the values are worth reporting
+            "o" | "title" if !value.is_empty() => unmapped.push(format!("{} ({})", attribute.name, value)),
+            "createtimestamp" => created = parse_timestamp(value),
+            "modifytimestamp" => updated = parse_timestamp(value),
+            name if PHONE_ATTRIBUTES.iter().any(|(n, _)| *n == name) => {
+                let label = PHONE_ATTRIBUTES.iter().find(|(n, _)| *n == name).map(|(_, l)| l.to_string());
+                let label = unique_label(label, phones.iter().map(|p| p.label.as_deref()));
+                match PhoneNumber::parse(label, value, default_country_code) {
+                    Some(phone) => phones.push(phone),
+                    None => unmapped.push(format!("{} ({})", name, value)),
+                }
+            },
+            name if STRUCTURAL.contains(&name) || value.is_empty() => {},
+            name => unmapped.push(name.to_string()),
+        }
+    }
+    // Multi-valued attributes are reported once
+    let mut seen = HashSet::new();
+    unmapped.retain(|name| seen.insert(name.clone()));
+
+    let mut contact = Contact::new(identity);
+    if let Some(id) = ["uid", "entryuuid"].iter().find_map(|name| first(name).and_then(|v| Uuid::parse_str(&v).ok())) {
+        contact.identifier = id;
+    }
+    contact.created = created.or(contact.created);
+    contact.updated = updated;
+    contact.address = address(&first);
+    contact.emails = Some(emails).filter(|v| !v.is_empty());
+    contact.phones = Some(phones).filter(|v| !v.is_empty());
+
+    Imported::new(contact, Vec::new(), unmapped)
+}
+
+/// The address of an entry, from the lines of its `postalAddress` (street,
+/// locality, then region and country), completed or overridden by its
+/// `street`, `l`, `st` and `postalCode` attributes.
+fn address(first: &dyn Fn(&str) -> Option<String>) -> Option<Address> {
+    let mut address = Address {
+        label: Some("default".to_string()),
+        country: None,
+        region: None,
+        city: None,
+        post_code: None,
+        street: None,
+        number: None,
+    };
+
+    if let Some(postal_address) = first("postaladdress") {
+        let lines: Vec<String> = postal_address
+            .split('$')
+            .map(|l| l.trim().replace("\\24", "$").replace("\\5C", "\\").replace("\\5c", "\\"))
+            .filter(|l| !l.is_empty())
+            .collect();
+        address.street = lines.first().cloned();
+        if let Some(line) = lines.get(1) {
+            // "75001 Paris" or just "Paris"
+            match line.split_once(' ') {
+                Some((code, city)) if code.chars().any(|c| c.is_ascii_digit()) => {
+                    address.post_code = Some(code.to_string());
+                    address.city = Some(city.trim().to_string());
+                },
+                _ => address.city = Some(line.clone()),
+            }
+        }
+        if lines.len() > 3 {
+            address.region = lines.get(2).cloned();
+        }
+        if lines.len() > 2 {
+            address.country = lines.last().cloned();
+        }
+    }
+
+    address.street = first("street").or(address.street);
+    address.city = first("l").or(address.city);
+    address.region = first("st").or(address.region);
+    address.post_code = first("postalcode").or(address.post_code);
+    if [&address.street, &address.city, &address.post_code, &address.country].iter().all(|f| f.is_none()) {
+        return None;
+    }
+
+    (address.number, address.street) = super::split_street(address.street.take());
+    Some(address)
+}
+
+/// Generalized time, as in `20240131120000Z`.
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value.get(..14)?, "%Y%m%d%H%M%S")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+/// Write contacts as inetOrgPerson entries under `base_dn`, named by their
+/// identifier (`uid=<identifier>,<base_dn>`), ready for `ldapadd`.
+pub fn write(contacts: &[&Contact], base_dn: &str, name: &NameFormat) -> String {
+    let mut out = String::from("version: 1\n");
+    for contact in contacts {
+        out.push('\n');
+        for (name, value) in to_attributes(contact, base_dn, name) {
+            write_line(&name, &value, &mut out);
+        }
+    }
+    out
+}
+
+/// The attributes of the entry of a contact, starting with its DN.
+fn to_attributes(contact: &Contact, base_dn: &str, name: &NameFormat) -> Vec<(String, String)> {
+    let identity = &contact.identity;
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    let mut push = |name: &str, value: &str| {
+        if !value.trim().is_empty() {
+            attributes.push((name.to_string(), value.to_string()));
+        }
+    };
+
+    push("dn", &format!("uid={},{}", contact.identifier, base_dn));
+    for class in OBJECT_CLASSES {
+        push("objectClass", class);
+    }
+    push("uid", &contact.identifier.to_string());
+
+    // cn and sn are required by the person class
+    let mut full_name = contact.format_name(name);
+    if full_name.trim().is_empty() {
+        full_name = contact.identifier.to_string();
+    }
+    push("cn", &full_name);
+    push("sn", identity.last_name.as_deref().filter(|l| !l.is_empty()).unwrap_or(&full_name));
+    push("givenName", identity.first_name.as_deref().unwrap_or_default());
+
+    for email in contact.emails.iter().flatten() {
+        push("mail", email.address.as_deref().unwrap_or_default());
+    }
+
+    for phone in contact.phones.iter().flatten() {
+        let label = phone.label.as_deref().unwrap_or_default().to_lowercase();
+        let name = if label.starts_with("mobile") || label.starts_with("cell") {
+            "mobile"
+        } else if label.starts_with("home") {
+            "homePhone"
+        } else {
+            "telephoneNumber"
+        };
+        push(name, &format!("+{}{}", phone.country_code, phone.number));
+    }
+
+    if let Some(address) = &contact.address {
+        let street = [&address.number, &address.street]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let locality = [&address.post_code, &address.city]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Lines are separated by `$`, which must itself be escaped
+        let lines: Vec<String> = [Some(street.clone()), Some(locality), address.region.clone(), address.country.clone()]
+            .into_iter()
+            .flatten()
+            .filter(|l| !l.is_empty())
+            .map(|l| l.replace('\\', "\\5C").replace('$', "\\24"))
+            .collect();
+        push("postalAddress", &lines.join("$"));
+        push("street", &street);
+        push("l", address.city.as_deref().unwrap_or_default());
+        push("st", address.region.as_deref().unwrap_or_default());
+        push("postalCode", address.post_code.as_deref().unwrap_or_default());
+    }
+
+    attributes
+}
+
+/// Write `name: value`, or `name:: <base64>` when the value is not a
+/// SAFE-STRING of RFC 2849, folding long lines.
+fn write_line(name: &str, value: &str, out: &mut String) {
+    let line = if is_safe(value) {
+        format!("{}: {}", name, value)
+    } else {
+        format!("{}:: {}", name, STANDARD.encode(value))
+    };
+
+    // Both forms are ASCII, so folding on bytes never splits a character
+    let mut rest = line.as_str();
+    let mut width = LINE_LENGTH;
+    while rest.len() > width {
+        let (head, tail) = rest.split_at(width);
+        out.push_str(head);
+        out.push_str("\n ");
+        rest = tail;
+        width = LINE_LENGTH - 1;
+    }
+    out.push_str(rest);
+    out.push('\n');
+}
+
+fn is_safe(value: &str) -> bool {
+    !value.starts_with([' ', ':', '<'])
+        && !value.ends_with(' ')
+        && value.bytes().all(|b| (1..0x80).contains(&b) && b != b'\n' && b != b'\r')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::name_format::FULL_NAME;
+
+    fn named(first: Option<&str>, middle: Option<&str>, last: Option<&str>) -> Contact {
+        Contact::new(Identity {
+            first_name: first.map(str::to_string),
+            middle_name: middle.map(str::to_string),
+            last_name: last.map(str::to_string),
+            ..Identity::default()
+        })
+    }
+
+    /// The first and last names of contacts written to LDIF and read back.
+    fn round_trip(contacts: &[Contact]) -> Vec<(Option<String>, Option<String>)> {
+        let text = write(&contacts.iter().collect::<Vec<_>>(), "ou=contacts,dc=example,dc=org", &FULL_NAME);
+        read(&text, 33).unwrap().contacts.into_iter()
+            .map(|imported| (imported.contact.identity.first_name, imported.contact.identity.last_name))
+            .collect()
+    }
+
+    #[test]
+    fn names_filling_required_attributes_are_not_read_back() {
+        let contacts = [
+            named(Some("Ada"), None, Some("Lovelace")),
+            named(Some("Ada"), None, None),
+            named(Some("Ada"), Some("Byron"), None),
+            named(None, None, Some("Turing")),
+            named(None, None, None),
+        ];
+        let name = |value: &str| Some(value.to_string());
+        assert_eq!(round_trip(&contacts), [
+            (name("Ada"), name("Lovelace")),
+            (name("Ada"), None),
+            (name("Ada"), None),
+            (None, name("Turing")),
+            (None, None),
+        ]);
+    }
+}
@@ -0,0 +1,155 @@
+pub mod vcard;
+pub mod csv;
+pub mod jcard;
+pub mod xcard;
+pub mod ldif;
+pub mod ics;
+pub mod site;
+pub mod abook;
+pub mod khard;
+pub mod takeout;
+
+use std::path::Path;
+use crate::contact::Contact;
+use crate::import::Importer;
+use crate::models::TuppData;
+use crate::name_format::NameFormat;
+use crate::error::TuppError;
+
+/// A file format contacts can be exported to.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// The tupp data file format.
+    Json,
+    /// vCard 4.0, for phones and mail clients.
+    Vcard,
+    /// CSV, with Google Contacts headers unless a mapping file is given.
+    Csv,
+    /// jCard (RFC 7095), vCard as JSON.
+    Jcard,
+    /// xCard (RFC 6351), vCard as XML.
+    Xcard,
+    /// LDIF, as inetOrgPerson entries for LDAP directories.
+    Ldif,
+    /// iCalendar, the birthdays and anniversaries as yearly events.
+    Ics,
+    /// A directory of HTML pages: an index by group, a page per contact and a phone list.
+    Html,
+    /// The same directory of pages, in Markdown.
+    Markdown,
+}
+
+impl ExportFormat {
+    /// The markup of the formats written as a directory of pages.
+    pub fn site_flavor(self) -> Option<site::Flavor> {
+        match self {
+            ExportFormat::Html => Some(site::Flavor::Html),
+            ExportFormat::Markdown => Some(site::Flavor::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A file format contacts can be imported from.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ImportFormat {
+    /// vCard 3.0 or 4.0.
+    Vcard,
+    /// CSV, with Google Contacts or Outlook headers, or a mapping file.
+    Csv,
+    /// LDIF, reading the inetOrgPerson entries.
+    Ldif,
+    /// The `addressbook` file of abook.
+    Abook,
+    /// A khard or vdirsyncer directory of vCard files.
+    Khard,
+    /// A Google Takeout export of Google Contacts, zipped or extracted.
+    Takeout,
+}
+
+impl ImportFormat {
+    /// Guess the format of a file from its extension, or of a directory from its content.
+    pub fn from_path(path: &str) -> Result<Self, TuppError> {
+        let location = Path::new(path);
+        if location.is_dir() {
+            let takeout = location.join("Contacts").is_dir()
+                || location.components().any(|c| c.as_os_str().to_string_lossy().starts_with("Takeout"));
+            return Ok(if takeout { Self::Takeout } else { Self::Khard });
+        }
+        if location.file_name().is_some_and(|name| name == "addressbook") {
+            return Ok(Self::Abook);
+        }
+
+        let extension = location
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("vcf") | Some("vcard") => Ok(Self::Vcard),
+            Some("csv") | Some("tsv") => Ok(Self::Csv),
+            Some("ldif") | Some("ldi") => Ok(Self::Ldif),
+            Some("zip") => Ok(Self::Takeout),
+            _ => Err(TuppError::Validation(format!("Cannot guess the format of '{}', please use --format", path))),
+        }
+    }
+
+    /// The importer of the format. `mapping` is only used by CSV.
+    pub fn importer(self, mapping: Option<csv::Mapping>) -> Box<dyn Importer> {
+        match self {
+            Self::Vcard => Box::new(vcard::VcardImporter),
+            Self::Csv => Box::new(csv::CsvImporter { mapping }),
+            Self::Ldif => Box::new(ldif::LdifImporter),
+            Self::Abook => Box::new(abook::AbookImporter),
+            Self::Khard => Box::new(khard::KhardImporter),
+            Self::Takeout => Box::new(takeout::TakeoutImporter),
+        }
+    }
+}
+
+/// The base DN of LDIF entries when none is given.
+pub const DEFAULT_BASE_DN: &str = "ou=contacts";
+
+/// Options of the formats that have some.
+#[derive(Default)]
+pub struct ExportOptions {
+    /// For CSV, the columns to write.
+    pub mapping: Option<csv::Mapping>,
+    /// For LDIF, the DN the entries are written under.
+    pub base_dn: Option<String>,
+}
+
+/// Write contacts in a format, resolving their groups and links in `data`.
+pub fn export(
+    format: ExportFormat,
+    contacts: &[&Contact],
+    data: &TuppData,
+    options: &ExportOptions,
+) -> Result<String, TuppError> {
+    // Full names follow the formal preset, which may be configured
+    let name = || NameFormat::resolve("formal");
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(contacts)?),
+        ExportFormat::Vcard => Ok(vcard::write(contacts, data, &name()?)),
+        ExportFormat::Csv => csv::write(contacts, data, options.mapping.as_ref(), &name()?),
+        ExportFormat::Jcard => Ok(serde_json::to_string_pretty(&jcard::write(contacts, data, &name()?))?),
+        ExportFormat::Xcard => Ok(xcard::write(contacts, data, &name()?)),
+        ExportFormat::Ldif => Ok(ldif::write(contacts, options.base_dn.as_deref().unwrap_or(DEFAULT_BASE_DN), &name()?)),
+        ExportFormat::Ics => Ok(ics::write(contacts)),
+        ExportFormat::Html | ExportFormat::Markdown => Err(TuppError::Validation(
+            "HTML and Markdown exports are directories, use 'tupp export --format html <dir>'".to_string(),
+        )),
+    }
+}
+
+/// Split the street number from the street, as in "12 Rue des Lilas".
+pub fn split_street(street: Option<String>) -> (Option<String>, Option<String>) {
+    match street {
+        Some(street) => match street.split_once(' ') {
+            Some((number, rest)) if number.chars().next().is_some_and(|c| c.is_ascii_digit()) => {
+                (Some(number.trim_end_matches(',').to_string()), Some(rest.trim().to_string()))
+            },
+            _ => (None, Some(street)),
+        },
+        None => (None, None),
+    }
+}
@@ -0,0 +1,388 @@
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+use crate::contact::Contact;
+use crate::group::Group;
+use crate::models::{Date, Gender, TuppData};
+use crate::name_format::NameFormat;
+use crate::error::TuppError;
+
+/// The markup of a static address book.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flavor {
+    Html,
+    Markdown,
+}
+
+impl Flavor {
+    fn extension(self) -> &'static str {
+        match self {
+            Flavor::Html => "html",
+            Flavor::Markdown => "md",
+        }
+    }
+}
+
+/// A piece of text, possibly a link.
+#[derive(Clone)]
+enum Span {
+    Text(String),
+    Link { text: String, href: String },
+}
+
+/// The content of a page, rendered in either flavor.
+enum Block {
+    Heading(u8, String),
+    Paragraph(Vec<Span>),
+    /// Items with their nesting depth.
+    List(Vec<(usize, Vec<Span>)>),
+    Fields(Vec<(String, Vec<Span>)>),
+    Table(Vec<String>, Vec<Vec<Vec<Span>>>),
+}
+
+struct Page {
+    title: String,
+    blocks: Vec<Block>,
+}
+
+fn text(value: impl Into<String>) -> Vec<Span> {
+    vec![Span::Text(value.into())]
+}
+
+fn link(value: impl Into<String>, href: impl Into<String>) -> Vec<Span> {
+    vec![Span::Link { text: value.into(), href: href.into() }]
+}
+
+/// Write a browsable address book in `dir`: an index of the contacts by
+/// group, one page per contact under `contacts/`, with links between related
+/// contacts, and a printable phone list.
+pub fn write(dir: &Path, flavor: Flavor, contacts: &[&Contact], data: &TuppData) -> Result<(), TuppError> {
+    let ext = flavor.extension();
+    let sort = NameFormat::resolve("sort")?;
+    let name = NameFormat::resolve("formal")?;
+    fs::create_dir_all(dir.join("contacts")).map_err(TuppError::Io)?;
+
+    let mut sorted: Vec<&Contact> = contacts.to_vec();
+    sorted.sort_by_cached_key(|c| c.format_name(&sort).to_lowercase());
+
+    let pages = [
+        (dir.join(format!("index.{}", ext)), index_page(&sorted, data, &name, ext)),
+        (dir.join(format!("phones.{}", ext)), phones_page(&sorted, &name, ext)),
+    ];
+    for (path, page) in pages {
+        fs::write(path, render(&page, flavor)).map_err(TuppError::Io)?;
+    }
+
+    for contact in &sorted {
+        let path = dir.join("contacts").join(format!("{}.{}", contact.identifier, ext));
+        fs::write(path, render(&contact_page(contact, contacts, data, &name, ext), flavor)).map_err(TuppError::Io)?;
+    }
+    Ok(())
+}
+
+fn index_page(contacts: &[&Contact], data: &TuppData, name: &NameFormat, ext: &str) -> Page {
+    let mut blocks = vec![Block::Paragraph(
+        [text(format!("{} contacts. ", contacts.len())), link("Phone list", format!("phones.{}", ext))].concat(),
+    )];
+
+    let mut items = Vec::new();
+    list_groups(&data.groups, contacts, name, 0, ext, &mut items);
+    if !items.is_empty() {
+        blocks.push(Block::Heading(2, "Groups".to_string()));
+        blocks.push(Block::List(items));
+    }
+
+    let ungrouped: Vec<(usize, Vec<Span>)> = contacts
+        .iter()
+        .filter(|c| c.groups.as_ref().is_none_or(|g| g.is_empty()))
+        .map(|c| (0, contact_link(c, name, "contacts/", ext)))
+        .collect();
+    if !ungrouped.is_empty() {
+        blocks.push(Block::Heading(2, "Other contacts".to_string()));
+        blocks.push(Block::List(ungrouped));
+    }
+
+    Page { title: "Address book".to_string(), blocks }
+}
+
+/// List the groups, each followed by its contacts and then its subgroups.
+fn list_groups(groups: &[Group], contacts: &[&Contact], name: &NameFormat, depth: usize, ext: &str, items: &mut Vec<(usize, Vec<Span>)>) {
+    let mut groups: Vec<&Group> = groups.iter().collect();
+    groups.sort_by_key(|g| g.name.to_lowercase());
+    for group in groups {
+        items.push((depth, text(group.name.clone())));
+        for contact in contacts.iter().filter(|c| c.groups.iter().flatten().any(|id| *id == group.identifier)) {
+            items.push((depth + 1, contact_link(contact, name, "contacts/", ext)));
+        }
+        list_groups(&group.subgroups, contacts, name, depth + 1, ext, items);
+    }
+}
+
+fn phones_page(contacts: &[&Contact], name: &NameFormat, ext: &str) -> Page {
+    let rows = contacts
+        .iter()
+        .flat_map(|contact| {
+            contact.phones.iter().flatten().map(move |phone| {
+                let number = format!("+{} {}", phone.country_code, phone.number);
+                vec![
+                    contact_link(contact, name, "contacts/", ext),
+                    text(phone.label.clone().unwrap_or_default()),
+                    link(number, format!("tel:+{}{}", phone.country_code, phone.number)),
+                ]
+            })
+        })
+        .collect();
+
+    Page {
+        title: "Phone list".to_string(),
+        blocks: vec![
+            Block::Paragraph(link("Address book", format!("index.{}", ext))),
+            Block::Table(vec!["Name".to_string(), "Label".to_string(), "Number".to_string()], rows),
+        ],
+    }
+}
+
+fn contact_page(contact: &Contact, contacts: &[&Contact], data: &TuppData, name: &NameFormat, ext: &str) -> Page {
+    let identity = &contact.identity;
+    let date = |d: &Option<Date>| d.as_ref().map(|d| text(d.to_string()));
+    let mut blocks = vec![Block::Paragraph(link("Address book", format!("../index.{}", ext)))];
+
+    let mut fields: Vec<(String, Vec<Span>)> = Vec::new();
+    let mut field = |label: &str, value: Option<Vec<Span>>| {
+        if let Some(value) = value {
+            fields.push((label.to_string(), value));
+        }
+    };
+    field("Title", identity.title.clone().map(text));
+    field("First name", identity.first_name.clone().map(text));
+    field("Middle name", identity.middle_name.clone().map(text));
+    field("Last name", identity.last_name.clone().map(text));
+    field("Post-nominal", identity.post_nominal.clone().map(text));
+    field("Gender", identity.gender.as_ref().map(|g| text(match g {
+        Gender::Male => "Male",
+        Gender::Female => "Female",
+        Gender::NonBinary => "Non-binary",
+    })));
+    field("Birth date", date(&identity.birth_date));
+    field("Birth location", identity.birth_location.as_ref().map(|l| text(l.to_string())));
+    let birth_name = [&identity.birth_first_name, &identity.birth_middle_name, &identity.birth_last_name]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    field("Birth name", Some(birth_name).filter(|n| !n.is_empty()).map(text));
+    field("Anniversary", date(&identity.anniversary));
+    if !identity.is_alive {
+        field("Death date", date(&identity.death_date).or_else(|| Some(text("Deceased"))));
+        field("Death location", identity.death_location.as_ref().map(|l| text(l.to_string())));
+    }
+    push_fields(&mut blocks, "Identity", fields);
+
+    let mut fields: Vec<(String, Vec<Span>)> = Vec::new();
+    for email in contact.emails.iter().flatten() {
+        if let Some(address) = &email.address {
+            let label = format!("Email ({})", email.label.as_deref().unwrap_or("default"));
+            fields.push((label, link(address.clone(), format!("mailto:{}", address))));
+        }
+    }
+    for phone in contact.phones.iter().flatten() {
+        let label = format!("Phone ({})", phone.label.as_deref().unwrap_or("default"));
+        let number = format!("+{} {}", phone.country_code, phone.number);
+        fields.push((label, link(number, format!("tel:+{}{}", phone.country_code, phone.number))));
+    }
+    if let Some(address) = &contact.address {
+        let label = format!("Address ({})", address.label.as_deref().unwrap_or("default"));
+        fields.push((label, text(address.to_string())));
+    }
+    for social in contact.socials.iter().flatten() {
+        fields.push((social.network.clone(), text(social.username.clone().unwrap_or_default())));
+    }
+    push_fields(&mut blocks, "Contact", fields);
+
+    let fields: Vec<(String, Vec<Span>)> = contact.links.iter().flatten()
+        .map(|l| {
+            let target = match contacts.iter().find(|c| c.identifier == l.target) {
+                Some(target) => contact_link(target, name, "", ext),
+                None => text(format!("unknown contact {}", l.target)),
+            };
+            (l.relation.to_string(), target)
+        })
+        .collect();
+    push_fields(&mut blocks, "Relationships", fields);
+
+    let mut groups: Vec<String> = contact.groups.iter().flatten()
+        .map(|id| group_name(&data.groups, id))
+        .collect();
+    groups.sort();
+    if !groups.is_empty() {
+        blocks.push(Block::Heading(2, "Groups".to_string()));
+        blocks.push(Block::List(groups.into_iter().map(|g| (0, text(g))).collect()));
+    }
+
+    let mut fields = vec![("Identifier".to_string(), text(contact.identifier.to_string()))];
+    if let Some(created) = contact.created {
+        fields.push(("Created".to_string(), text(created.format("%Y-%m-%d").to_string())));
+    }
+    if let Some(updated) = contact.updated {
+        fields.push(("Updated".to_string(), text(updated.format("%Y-%m-%d").to_string())));
+    }
+    push_fields(&mut blocks, "Record", fields);
+
+    Page { title: contact.format_name(name), blocks }
+}
+
+fn push_fields(blocks: &mut Vec<Block>, title: &str, fields: Vec<(String, Vec<Span>)>) {
+    if !fields.is_empty() {
+        blocks.push(Block::Heading(2, title.to_string()));
+        blocks.push(Block::Fields(fields));
+    }
+}
+
+fn contact_link(contact: &Contact, name: &NameFormat, prefix: &str, ext: &str) -> Vec<Span> {
+    link(contact.format_name(name), format!("{}{}.{}", prefix, contact.identifier, ext))
+}
+
+fn group_name(groups: &[Group], id: &Uuid) -> String {
+    Group::find_group_by_id_recursive(groups, id)
+        .map(|g| g.name.clone())
+        .unwrap_or_else(|| format!("unknown group {}", id))
+}
+
+fn render(page: &Page, flavor: Flavor) -> String {
+    match flavor {
+        Flavor::Html => render_html(page),
+        Flavor::Markdown => render_markdown(page),
+    }
+}
+
+/// Styles of the HTML pages; the print rules keep the phone list on paper
+/// without the navigation links.
+const STYLE: &str = "body{font-family:sans-serif;max-width:50em;margin:2em auto;padding:0 1em;line-height:1.5}\
+th,td{text-align:left;padding:.2em 1em .2em 0;vertical-align:top}\
+th{color:#555;font-weight:normal}table{border-collapse:collapse}\
+tbody tr:nth-child(even){background:#f4f4f4}ul ul{margin:0}\
+@media print{body{margin:0;max-width:none}p.nav{display:none}a{color:inherit;text-decoration:none}}";
+
+fn render_html(page: &Page) -> String {
+    let spans = |spans: &[Span]| -> String {
+        spans.iter().map(|span| match span {
+            Span::Text(t) => escape_html(t),
+            Span::Link { text, href } => format!("<a href=\"{}\">{}</a>", escape_html(href), escape_html(text)),
+        }).collect()
+    };
+
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape_html(&page.title), STYLE, escape_html(&page.title),
+    );
+    for (i, block) in page.blocks.iter().enumerate() {
+        match block {
+            Block::Heading(level, title) => out.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape_html(title))),
+            Block::Paragraph(content) if i == 0 => out.push_str(&format!("<p class=\"nav\">{}</p>\n", spans(content))),
+            Block::Paragraph(content) => out.push_str(&format!("<p>{}</p>\n", spans(content))),
+            Block::List(items) => {
+                let mut depth = 0;
+                out.push_str("<ul>\n");
+                for (j, (item_depth, content)) in items.iter().enumerate() {
+                    while depth < *item_depth {
+                        out.push_str("<ul>\n");
+                        depth += 1;
+                    }
+                    while depth > *item_depth {
+                        out.push_str("</ul></li>\n");
+                        depth -= 1;
+                    }
+                    out.push_str(&format!("<li>{}", spans(content)));
+                    // Items followed by deeper ones stay open for their sublist
+                    if items.get(j + 1).is_none_or(|(next, _)| next <= item_depth) {
+                        out.push_str("</li>\n");
+                    }
+                }
+                while depth > 0 {
+                    out.push_str("</ul></li>\n");
+                    depth -= 1;
+                }
+                out.push_str("</ul>\n");
+            },
+            Block::Fields(fields) => {
+                out.push_str("<table>\n");
+                for (label, value) in fields {
+                    out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", escape_html(label), spans(value)));
+                }
+                out.push_str("</table>\n");
+            },
+            Block::Table(headers, rows) => {
+                out.push_str("<table>\n<thead><tr>");
+                for header in headers {
+                    out.push_str(&format!("<th>{}</th>", escape_html(header)));
+                }
+                out.push_str("</tr></thead>\n<tbody>\n");
+                for row in rows {
+                    out.push_str("<tr>");
+                    for cell in row {
+                        out.push_str(&format!("<td>{}</td>", spans(cell)));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</tbody>\n</table>\n");
+            },
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn render_markdown(page: &Page) -> String {
+    let spans = |spans: &[Span]| -> String {
+        spans.iter().map(|span| match span {
+            Span::Text(t) => escape_markdown(t),
+            Span::Link { text, href } => format!("[{}](<{}>)", escape_markdown(text), href),
+        }).collect()
+    };
+
+    let mut out = format!("# {}\n", escape_markdown(&page.title));
+    for block in &page.blocks {
+        out.push('\n');
+        match block {
+            Block::Heading(level, title) => out.push_str(&format!("{} {}\n", "#".repeat(*level as usize), escape_markdown(title))),
+            Block::Paragraph(content) => out.push_str(&format!("{}\n", spans(content))),
+            Block::List(items) => {
+                for (depth, content) in items {
+                    out.push_str(&format!("{}- {}\n", "  ".repeat(*depth), spans(content)));
+                }
+            },
+            Block::Fields(fields) => {
+                for (label, value) in fields {
+                    out.push_str(&format!("- **{}:** {}\n", escape_markdown(label), spans(value)));
+                }
+            },
+            Block::Table(headers, rows) => {
+                out.push_str(&format!("| {} |\n", headers.iter().map(|h| escape_markdown(h)).collect::<Vec<_>>().join(" | ")));
+                out.push_str(&format!("|{}\n", "---|".repeat(headers.len())));
+                for row in rows {
+                    out.push_str(&format!("| {} |\n", row.iter().map(|c| spans(c)).collect::<Vec<_>>().join(" | ")));
+                }
+            },
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>|#".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::import::{read_text, Fragment, Imported, Importer};
+use crate::error::TuppError;
+
+/// Labels of Google Contacts that hold every contact rather than a group.
+const ALL_CONTACTS: &[&str] = &["All Contacts", "My Contacts", "Contacts"];
+
+/// Categories Google adds to the cards of its system groups.
+const SYSTEM_CATEGORIES: &[&str] = &["myContacts", "starred"];
+
+/// Reads a Google Takeout export of Google Contacts, as the downloaded
+/// `.zip` archive or its extracted directory: a `.vcf` file per label, in
+/// `Takeout/Contacts/<label>/`.
+pub struct TakeoutImporter;
+
+impl Importer for TakeoutImporter {
+    fn name(&self) -> &'static str {
+        "takeout"
+    }
+
+    /// Read the cards of every label. A contact appears once per label, so
+    /// the cards of a contact are read as one contact with all its labels as groups.
+    fn read(&self, path: &Path, default_country_code: u16) -> Result<Fragment, TuppError> {
+        let files = if path.is_dir() { read_dir(path)? } else { read_zip(path)? };
+        if files.is_empty() {
+            return Err(TuppError::Validation(format!("No vCard file found in {}", path.display())));
+        }
+
+        let mut fragment = Fragment::default();
+        for (label, text) in files {
+            let cards = super::vcard::read(&text, default_country_code)?;
+            fragment.report.extend(cards.report);
+            for mut imported in cards.contacts {
+                imported.groups.retain(|g| !SYSTEM_CATEGORIES.iter().any(|s| s.eq_ignore_ascii_case(g)));
+                if let Some(label) = label.as_ref().filter(|l| !ALL_CONTACTS.contains(&l.as_str())) {
+                    imported.groups.push(label.clone());
+                }
+                add(&mut fragment.contacts, imported);
+            }
+        }
+        Ok(fragment)
+    }
+}
+
+/// Add a contact, or only its groups if it was read from another label.
+fn add(contacts: &mut Vec<Imported>, imported: Imported) {
+    let key = imported.source_key();
+    match contacts.iter_mut().find(|c| c.source_key() == key) {
+        Some(known) => {
+            for group in imported.groups {
+                if !known.groups.contains(&group) {
+                    known.groups.push(group);
+                }
+            }
+        },
+        None => contacts.push(imported),
+    }
+}
+
+/// The label and text of the `.vcf` files of an extracted export.
+fn read_dir(dir: &Path) -> Result<Vec<(Option<String>, String)>, TuppError> {
+    super::khard::vcf_files(dir)?
+        .iter()
+        .map(|file| Ok((label(file), read_text(file)?)))
+        .collect()
+}
+
+/// The label and text of the `.vcf` files of an export archive.
+fn read_zip(path: &Path) -> Result<Vec<(Option<String>, String)>, TuppError> {
+    let invalid = |e: zip::result::ZipError| TuppError::Validation(format!("Cannot read {}: {}", path.display(), e));
+    let mut archive = zip::ZipArchive::new(File::open(path)?).map_err(invalid)?;
+
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(invalid)?;
+        let Some(name) = file.enclosed_name() else { continue };
+        if !file.is_file() || !name.extension().is_some_and(|e| e.eq_ignore_ascii_case("vcf")) {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        files.push((label(&name), String::from_utf8_lossy(&bytes).into_owned()));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+/// The label of a file, from the name of its directory.
+fn label(file: &Path) -> Option<String> {
+    file.parent()
+        .and_then(|parent| parent.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
@@ -0,0 +1,808 @@
+use std::path::Path;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use uuid::Uuid;
+use crate::contact::{Contact, Relation};
+use crate::group::Group;
+use crate::import::{read_text, Fragment, Imported, Importer};
+use crate::merge::unique_label;
+use crate::name_format::NameFormat;
+use crate::models::{Address, Date, Email, Gender, Identity, PhoneNumber, Social, TuppData};
+use crate::error::TuppError;
+
+/// A content line of a vCard: `NAME;PARAM=value:value`.
+#[derive(Debug)]
+pub struct Property {
+    pub name: String,
+    /// Parameter names are uppercase. A parameter with several values
+    /// (`TYPE=work,voice`) appears once per value.
+    pub params: Vec<(String, String)>,
+    /// The raw value, still escaped, but decoded from quoted-printable.
+    pub value: String,
+}
+
+impl Property {
+    fn new(name: &str, value: String) -> Self {
+        Self { name: name.to_string(), params: Vec::new(), value }
+    }
+
+    fn with(mut self, param: &str, value: impl Into<Option<String>>) -> Self {
+        if let Some(value) = value.into() {
+            self.params.push((param.to_string(), value));
+        }
+        self
+    }
+
+    pub fn param<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.params.iter().filter(move |(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The `TYPE` values, lowercase.
+    fn types(&self) -> Vec<String> {
+        self.param("TYPE").map(str::to_lowercase).collect()
+    }
+
+    fn is_preferred(&self) -> bool {
+        self.types().iter().any(|t| t == "pref") || self.param("PREF").next().is_some()
+    }
+
+    /// The first `TYPE` that can serve as a label, like `work` or `cell`.
+    fn label(&self) -> Option<String> {
+        const NOT_LABELS: &[&str] = &["pref", "internet", "voice", "text", "x400", "msg"];
+        self.types().into_iter().find(|t| !NOT_LABELS.contains(&t.as_str()))
+    }
+
+    /// Unescaped components of a structured value (`N`, `ADR`, `GENDER`).
+    pub fn components(&self) -> Vec<String> {
+        split_escaped(&self.value, ';').iter().map(|c| unescape(c)).collect()
+    }
+
+    /// Unescaped values of a property holding a list (`CATEGORIES`).
+    pub fn list(&self) -> Vec<String> {
+        split_escaped(&self.value, ',').iter().map(|c| unescape(c)).collect()
+    }
+
+    pub fn text(&self) -> String {
+        unescape(&self.value)
+    }
+
+    /// Whether the value has components, such as `N` and `ADR`.
+    pub fn is_structured(&self) -> bool {
+        matches!(self.name.as_str(), "N" | "ADR" | "GENDER")
+    }
+
+    /// Whether the value is a list, such as `CATEGORIES`.
+    pub fn is_list(&self) -> bool {
+        matches!(self.name.as_str(), "CATEGORIES" | "NICKNAME")
+    }
+
+    /// The value type of RFC 6350, as named in jCard and xCard.
+    pub fn value_type(&self) -> String {
+        if let Some(value) = self.param("VALUE").next() {
+            return value.to_lowercase();
+        }
+        match self.name.as_str() {
+            "UID" | "RELATED" | "URL" | "PHOTO" => "uri",
+            "BDAY" | "ANNIVERSARY" | "DEATHDATE" => "date",
+            "REV" => "timestamp",
+            name if name.starts_with("X-") => "unknown",
+            _ => "text",
+        }.to_string()
+    }
+}
+
+/// Properties that need no mapping, or whose value is used elsewhere.
+const STRUCTURAL: &[&str] = &["BEGIN", "END", "VERSION", "PRODID", "FN", "N", "KIND", "UID", "REV", "CLIENTPIDMAP"];
+
+/// The contact field of each imported property, for the mapping report.
+const TARGETS: &[(&str, &str)] = &[
+    ("N", "name"), ("FN", "name"), ("EMAIL", "emails"), ("TEL", "phones"), ("ADR", "address"),
+    ("BDAY", "birth date"), ("ANNIVERSARY", "anniversary"), ("DEATHDATE", "death date"),
+    ("GENDER", "gender"), ("X-GENDER", "gender"), ("CATEGORIES", "groups"),
+    ("X-SOCIALPROFILE", "socials"), ("IMPP", "socials"),
+    ("UID", "identifier"), ("REV", "updated"),
+];
+
+/// Networks of the `X-<NETWORK>` properties written by older clients.
+const LEGACY_SOCIALS: &[&str] = &["X-TWITTER", "X-SKYPE", "X-SKYPE-USERNAME", "X-JABBER", "X-AIM", "X-ICQ", "X-MSN", "X-YAHOO", "X-GOOGLE-TALK"];
+
+/// Split a vCard file, in version 3.0 or 4.0 (and mostly 2.1), into the
+/// properties of each card.
+pub fn parse(text: &str) -> Result<Vec<Vec<Property>>, TuppError> {
+    let mut cards = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+
+    for line in unfold(text) {
+        let property = match parse_line(&line) {
+            Some(property) => property,
+            None => continue,
+        };
+        match (property.name.as_str(), property.value.trim().to_uppercase().as_str()) {
+            ("BEGIN", "VCARD") => current = Some(Vec::new()),
+            ("END", "VCARD") => {
+                let card = current.take()
+                    .ok_or_else(|| TuppError::Validation("END:VCARD without BEGIN:VCARD".to_string()))?;
+                cards.push(card);
+            },
+            _ => {
+                if let Some(card) = current.as_mut() {
+                    card.push(property);
+                }
+            },
+        }
+    }
+
+    if current.is_some() {
+        return Err(TuppError::Validation("Missing END:VCARD at the end of the file".to_string()));
+    }
+    Ok(cards)
+}
+
+/// Join folded lines, and quoted-printable soft line breaks.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for raw in text.lines() {
+        match lines.last_mut() {
+            Some(last) if is_quoted_printable(last) && last.ends_with('=') => {
+                last.pop();
+                last.push_str(raw);
+            },
+            Some(last) if raw.starts_with([' ', '\t']) => last.push_str(&raw[1..]),
+            _ if raw.trim().is_empty() => {},
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+fn is_quoted_printable(line: &str) -> bool {
+    line.split_once(':')
+        .is_some_and(|(head, _)| head.to_uppercase().contains("QUOTED-PRINTABLE"))
+}
+
+fn parse_line(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside of a quoted parameter value
+    let mut quoted = false;
+    let colon = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?.0;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = split_quoted(head, ';').into_iter();
+    let name = parts.next()?;
+    // Drop the group prefix of `item1.EMAIL`
+    let name = name.rsplit('.').next().unwrap_or(&name).trim().to_uppercase();
+
+    let mut params = Vec::new();
+    for part in parts {
+        match part.split_once('=') {
+            Some((key, values)) => {
+                for value in split_quoted(values, ',') {
+                    params.push((key.trim().to_uppercase(), value.trim_matches('"').to_string()));
+                }
+            },
+            // vCard 2.1 bare types: `TEL;WORK;VOICE:`
+            None => params.push(("TYPE".to_string(), part.trim().to_string())),
+        }
+    }
+
+    let mut property = Property { name, params, value: value.to_string() };
+    if property.param("ENCODING").any(|e| e.eq_ignore_ascii_case("QUOTED-PRINTABLE")) {
+        let latin1 = property.param("CHARSET").any(|c| c.eq_ignore_ascii_case("ISO-8859-1"));
+        property.value = decode_quoted_printable(&property.value, latin1);
+    }
+    Some(property)
+}
+
+/// Split on a separator, except inside double quotes.
+fn split_quoted(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                parts.last_mut().unwrap().push(c);
+            },
+            c if c == separator && !quoted => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Split on a separator, except when escaped with a backslash.
+fn split_escaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().unwrap();
+        match c {
+            '\\' => {
+                part.push(c);
+                part.extend(chars.next());
+            },
+            c if c == separator => parts.push(String::new()),
+            c => part.push(c),
+        }
+    }
+    parts
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(escaped) => out.push(escaped),
+            None => {},
+        }
+    }
+    out
+}
+
+fn decode_quoted_printable(text: &str, latin1: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            if let Some(byte) = bytes.get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    if latin1 {
+        decoded.into_iter().map(char::from).collect()
+    } else {
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+}
+
+/// Reads vCard files.
+pub struct VcardImporter;
+
+impl Importer for VcardImporter {
+    fn name(&self) -> &'static str {
+        "vcard"
+    }
+
+    fn read(&self, path: &Path, default_country_code: u16) -> Result<Fragment, TuppError> {
+        read(&read_text(path)?, default_country_code)
+    }
+}
+
+/// Read the cards of a vCard file as contacts, keyed by their `UID`.
+pub fn read(text: &str, default_country_code: u16) -> Result<Fragment, TuppError> {
+    let mut fragment = Fragment::default();
+    for card in parse(text)? {
+        for property in card.iter().filter(|p| !matches!(p.name.as_str(), "BEGIN" | "END" | "VERSION" | "PRODID")) {
+            let target = TARGETS.iter()
+                .find(|(name, _)| *name == property.name)
+                .map(|(_, target)| *target)
+                .or_else(|| LEGACY_SOCIALS.contains(&property.name.as_str()).then_some("socials"));
+            fragment.report.record(&property.name, target);
+        }
+
+        let mut imported = to_contact(&card, default_country_code);
+        imported.source_uid = card.iter()
+            .find(|p| p.name == "UID")
+            .map(|p| p.text().trim().to_string())
+            .filter(|uid| !uid.is_empty());
+        fragment.contacts.push(imported);
+    }
+    Ok(fragment)
+}
+
+/// Map the properties of a card to a contact. Properties that have no
+/// equivalent, or values that cannot be read, are listed in `unmapped`.
+pub fn to_contact(properties: &[Property], default_country_code: u16) -> Imported {
+    let mut identity = Identity::default();
+    let mut contact_id = None;
+    let mut updated = None;
+    let mut emails: Vec<Email> = Vec::new();
+    let mut phones: Vec<PhoneNumber> = Vec::new();
+    let mut socials: Vec<Social> = Vec::new();
+    let mut addresses: Vec<&Property> = Vec::new();
+    let mut groups = Vec::new();
+    let mut unmapped = Vec::new();
+
+    if let Some(n) = properties.iter().find(|p| p.name == "N") {
+        let parts = n.components();
+        let part = |i: usize| {
+            parts.get(i)
+                .map(|p| split_escaped(p, ',').iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" "))
+                .filter(|p| !p.is_empty())
+        };
+        identity.last_name = part(0);
+        identity.first_name = part(1);
+        identity.middle_name = part(2);
+        identity.title = part(3);
+        identity.post_nominal = part(4);
+    }
+    if identity.first_name.is_none() && identity.last_name.is_none() {
+        if let Some(full_name) = properties.iter().find(|p| p.name == "FN") {
+            let full_name = full_name.text();
+            let mut words = full_name.split_whitespace();
+            identity.first_name = words.next().map(str::to_string);
+            let rest = words.collect::<Vec<_>>().join(" ");
+            identity.last_name = Some(rest).filter(|r| !r.is_empty());
+        }
+    }
+
+    for property in properties {
+        match property.name.as_str() {
+            "UID" => {
+                let uid = property.text();
+                contact_id = Uuid::parse_str(uid.trim().trim_start_matches("urn:uuid:")).ok();
+            },
+            "REV" => updated = parse_timestamp(&property.text()),
+            "EMAIL" => {
+                let address = property.text().trim().trim_start_matches("mailto:").to_string();
+                if address.is_empty() {
+                    continue;
+                }
+                let label = unique_label(
+                    Some(property.label().unwrap_or_else(|| "default".to_string())),
+                    emails.iter().map(|e| e.label.as_deref()),
+                );
+                let email = Email { label, address: Some(address) };
+                if property.is_preferred() { emails.insert(0, email) } else { emails.push(email) }
+            },
+            "TEL" => {
+                let label = unique_label(
+                    Some(property.label().unwrap_or_else(|| "default".to_string())),
+                    phones.iter().map(|p| p.label.as_deref()),
+                );
+                match PhoneNumber::parse(label, &property.text(), default_country_code) {
+                    Some(phone) if property.is_preferred() => phones.insert(0, phone),
+                    Some(phone) => phones.push(phone),
+                    None => unmapped.push(format!("TEL ({})", property.text())),
+                }
+            },
+            "ADR" => addresses.push(property),
+            "BDAY" => match parse_date(&property.text()) {
+                Some(date) => identity.birth_date = Some(date),
+                None => unmapped.push(format!("BDAY ({})", property.text())),
+            },
+            "ANNIVERSARY" => match parse_date(&property.text()) {
+                Some(date) => identity.anniversary = Some(date),
+                None => unmapped.push(format!("ANNIVERSARY ({})", property.text())),
+            },
+            "DEATHDATE" => match parse_date(&property.text()) {
+                Some(date) => {
+                    identity.death_date = Some(date);
+                    identity.is_alive = false;
+                },
+                None => unmapped.push(format!("DEATHDATE ({})", property.text())),
+            },
+            "GENDER" | "X-GENDER" => {
+                let sex = property.components().into_iter().next().unwrap_or_default().to_lowercase();
+                identity.gender = match sex.as_str() {
+                    "m" | "male" => Some(Gender::Male),
+                    "f" | "female" => Some(Gender::Female),
+                    "o" | "other" | "non-binary" => Some(Gender::NonBinary),
+                    _ => None,
+                };
+            },
+            "CATEGORIES" => {
+                for category in split_escaped(&property.value, ',') {
+                    let category = unescape(&category).trim().to_string();
+                    if !category.is_empty() && !groups.contains(&category) {
+                        groups.push(category);
+                    }
+                }
+            },
+            "X-SOCIALPROFILE" | "IMPP" => match social(property) {
+                Some(social) => socials.push(social),
+                None => unmapped.push(format!("{} ({})", property.name, property.text())),
+            },
+            name if LEGACY_SOCIALS.contains(&name) => {
+                let network = name.trim_start_matches("X-").trim_end_matches("-USERNAME").to_lowercase();
+                socials.push(Social { label: property.label(), network, username: Some(property.text()) });
+            },
+            name if STRUCTURAL.contains(&name) => {},
+            name => unmapped.push(name.to_string()),
+        }
+    }
+
+    socials.dedup_by(|a, b| a.network.eq_ignore_ascii_case(&b.network) && a.username == b.username);
+
+    // A contact has a single address: the preferred one, or the first one
+    addresses.sort_by_key(|p| !p.is_preferred());
+    let mut addresses = addresses.into_iter();
+    let address = addresses.next().map(address);
+    unmapped.extend(addresses.map(|_| "ADR (only one address is kept)".to_string()));
+
+    let mut contact = Contact::new(identity);
+    if let Some(id) = contact_id {
+        contact.identifier = id;
+    }
+    contact.updated = updated;
+    contact.address = address;
+    contact.emails = Some(emails).filter(|v| !v.is_empty());
+    contact.phones = Some(phones).filter(|v| !v.is_empty());
+    contact.socials = Some(socials).filter(|v| !v.is_empty());
+
+    Imported::new(contact, groups, unmapped)
+}
+
+fn address(property: &Property) -> Address {
+    let parts = property.components();
+    let part = |i: usize| parts.get(i).map(|p| p.replace('\n', ", ").trim().to_string()).filter(|p| !p.is_empty());
+
+    let (number, street) = super::split_street(part(2));
+
+    Address {
+        label: Some(property.label().unwrap_or_else(|| "default".to_string())),
+        country: part(6),
+        region: part(4),
+        city: part(3),
+        post_code: part(5),
+        street,
+        number,
+    }
+}
+
+/// Read a social profile from `X-SOCIALPROFILE;TYPE=twitter:https://twitter.com/jdoe`
+/// or `IMPP:xmpp:jdoe@example.com`.
+fn social(property: &Property) -> Option<Social> {
+    let value = property.text();
+    let value = value.trim();
+
+    if property.name == "IMPP" {
+        let (scheme, username) = value.split_once(':')?;
+        return Some(Social {
+            label: property.label(),
+            network: scheme.to_lowercase(),
+            username: Some(username.to_string()),
+        });
+    }
+
+    let network = property.label().or_else(|| {
+        let host = value.split("://").nth(1)?.split('/').next()?;
+        host.trim_start_matches("www.").split('.').next().map(str::to_string)
+    })?;
+    let username = match property.param("X-USER").next() {
+        Some(user) => user.to_string(),
+        None => value
+            .trim_start_matches("x-apple:")
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()?
+            .trim_start_matches('@')
+            .to_string(),
+    };
+
+    Some(Social { label: None, network, username: Some(username).filter(|u| !u.is_empty()) })
+}
+
+/// Read the date forms of vCard 3.0 and 4.0: `1990-06-18`, `19900618`,
+/// `--0618` or `--06-18` without a year, `---18` with the day only, and
+/// dates followed by a time.
+pub fn parse_date(value: &str) -> Option<Date> {
+    let value = value.trim().split('T').next()?;
+    if let Some(day) = value.strip_prefix("---") {
+        if day.len() != 2 || !day.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        return format!("0-0-{}", day).parse().ok();
+    }
+
+    let (year, rest) = match value.strip_prefix("--") {
+        Some(rest) => (None, rest),
+        None => {
+            let digits = value.get(..4)?;
+            (Some(digits.parse::<i32>().ok()?), value[4..].trim_start_matches('-'))
+        },
+    };
+
+    let digits: String = rest.chars().filter(|c| *c != '-').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (month, day) = match digits.len() {
+        0 => (None, None),
+        2 => (Some(digits.parse::<u8>().ok()?), None),
+        4 => (Some(digits[..2].parse::<u8>().ok()?), Some(digits[2..].parse::<u8>().ok()?)),
+        _ => return None,
+    };
+
+    let date = format!("{}-{}-{}", year.unwrap_or(0), month.unwrap_or(0), day.unwrap_or(0));
+    date.parse().ok()
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").map(|t| t.and_utc()))
+        .ok()
+}
+
+/// Write contacts as vCard 4.0 (RFC 6350, with the dates and places of
+/// RFC 6474). Links become `RELATED` properties and groups `CATEGORIES`.
+pub fn write(contacts: &[&Contact], data: &TuppData, name: &NameFormat) -> String {
+    contacts.iter().map(|contact| write_card(&to_properties(contact, data, name))).collect()
+}
+
+fn write_card(properties: &[Property]) -> String {
+    let mut out = String::new();
+    fold_line("BEGIN:VCARD", &mut out);
+    for property in properties {
+        fold_line(&format_property(property), &mut out);
+    }
+    fold_line("END:VCARD", &mut out);
+    out
+}
+
+/// The properties of a contact, with escaped values. `name` formats the full name.
+pub fn to_properties(contact: &Contact, data: &TuppData, name: &NameFormat) -> Vec<Property> {
+    let identity = &contact.identity;
+    let mut properties = vec![
+        Property::new("VERSION", "4.0".to_string()),
+        Property::new("UID", format!("urn:uuid:{}", contact.identifier)),
+    ];
+
+    let full_name = contact.format_name(name);
+    let full_name = if full_name.is_empty() {
+        contact.emails.iter().flatten().find_map(|e| e.address.clone()).unwrap_or_default()
+    } else {
+        full_name
+    };
+    properties.push(Property::new("FN", escape(&full_name)));
+    properties.push(Property::new("N", structured(&[
+        &identity.last_name,
+        &identity.first_name,
+        &identity.middle_name,
+        &identity.title,
+        &identity.post_nominal,
+    ])));
+
+    if let Some(gender) = &identity.gender {
+        let sex = match gender {
+            Gender::Male => "M",
+            Gender::Female => "F",
+            Gender::NonBinary => "O",
+        };
+        properties.push(Property::new("GENDER", sex.to_string()));
+    }
+
+    let dates = [
+        ("BDAY", &identity.birth_date),
+        ("ANNIVERSARY", &identity.anniversary),
+        ("DEATHDATE", &identity.death_date),
+    ];
+    for (name, date) in dates {
+        if let Some(date) = date.as_ref().and_then(format_date) {
+            properties.push(Property::new(name, date));
+        }
+    }
+    let places = [("BIRTHPLACE", &identity.birth_location), ("DEATHPLACE", &identity.death_location)];
+    for (name, place) in places {
+        if let Some(place) = place {
+            properties.push(Property::new(name, escape(&place_text(place))));
+        }
+    }
+
+    for (i, email) in contact.emails.iter().flatten().enumerate() {
+        if let Some(address) = &email.address {
+            properties.push(
+                Property::new("EMAIL", escape(address))
+                    .with("TYPE", type_param(email.label.as_deref()))
+                    .with("PREF", (i == 0).then(|| "1".to_string())),
+            );
+        }
+    }
+
+    for (i, phone) in contact.phones.iter().flatten().enumerate() {
+        let label = phone.label.as_deref().map(|l| if l.eq_ignore_ascii_case("mobile") { "cell" } else { l });
+        properties.push(
+            Property::new("TEL", format!("tel:+{}{}", phone.country_code, phone.number))
+                .with("VALUE", "uri".to_string())
+                .with("TYPE", type_param(label))
+                .with("PREF", (i == 0).then(|| "1".to_string())),
+        );
+    }
+
+    if let Some(address) = &contact.address {
+        let street = [&address.number, &address.street]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        properties.push(
+            Property::new("ADR", structured(&[
+                &None,
+                &None,
+                &Some(street).filter(|s| !s.is_empty()),
+                &address.city,
+                &address.region,
+                &address.post_code,
+                &address.country,
+            ]))
+            .with("TYPE", type_param(address.label.as_deref())),
+        );
+    }
+
+    for social in contact.socials.iter().flatten() {
+        if let Some(username) = &social.username {
+            properties.push(
+                Property::new("X-SOCIALPROFILE", escape(username))
+                    .with("TYPE", type_param(Some(&social.network))),
+            );
+        }
+    }
+
+    for link in contact.links.iter().flatten() {
+        properties.push(
+            Property::new("RELATED", format!("urn:uuid:{}", link.target))
+                .with("VALUE", "uri".to_string())
+                .with("TYPE", related_type(&link.relation).to_string()),
+        );
+    }
+
+    let mut categories: Vec<String> = contact.groups.iter().flatten()
+        .filter_map(|id| Group::find_group_by_id_recursive(&data.groups, id))
+        .map(|group| escape(&group.name))
+        .collect();
+    categories.sort();
+    if !categories.is_empty() {
+        properties.push(Property::new("CATEGORIES", categories.join(",")));
+    }
+
+    if let Some(updated) = contact.updated.or(contact.created) {
+        properties.push(Property::new("REV", updated.format("%Y%m%dT%H%M%SZ").to_string()));
+    }
+
+    properties
+}
+
+fn format_property(property: &Property) -> String {
+    let mut line = property.name.clone();
+
+    // Parameters with several values are written once: `TYPE=work,voice`
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in &property.params {
+        if !names.contains(&name.as_str()) {
+            names.push(name);
+        }
+    }
+    for name in names {
+        let values: Vec<String> = property.param(name)
+            .map(|v| if v.contains([':', ';', ',']) { format!("\"{}\"", v.replace('"', "'")) } else { v.to_string() })
+            .collect();
+        line.push_str(&format!(";{}={}", name, values.join(",")));
+    }
+
+    line.push(':');
+    line.push_str(&property.value);
+    line
+}
+
+/// Append a content line, folded so that no line exceeds 75 octets.
+pub fn fold_line(line: &str, out: &mut String) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            length = 1;
+        }
+        out.push(c);
+        length += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// A structured value, such as `N` or `ADR`, whose components are separated by `;`.
+fn structured(components: &[&Option<String>]) -> String {
+    components
+        .iter()
+        .map(|c| c.as_deref().map(escape).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// A label usable as a `TYPE`: `default` and labels that are not simple
+/// words are dropped.
+fn type_param(label: Option<&str>) -> Option<String> {
+    let label = label?.trim().to_lowercase();
+    let simple = !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    (simple && label != "default").then_some(label)
+}
+
+/// The closest `RELATED` type of RFC 6350.
+fn related_type(relation: &Relation) -> &'static str {
+    match relation {
+        Relation::Friend => "friend",
+        Relation::Child => "child",
+        Relation::Parent => "parent",
+        Relation::Boss | Relation::Employee => "co-worker",
+        Relation::Colleague => "colleague",
+        Relation::Partner => "sweetheart",
+        Relation::Spouse => "spouse",
+        Relation::Ex => "acquaintance",
+    }
+}
+
+/// Format a date the RFC 6350 way, where unknown parts are left out:
+/// `19900618`, `--0618` without a year, `1990-06` without a day.
+pub fn format_date(date: &Date) -> Option<String> {
+    match (date.year, date.month, date.day) {
+        (Some(y), Some(m), Some(d)) => Some(format!("{:04}{:02}{:02}", y, m, d)),
+        (Some(y), Some(m), None) => Some(format!("{:04}-{:02}", y, m)),
+        (Some(y), None, _) => Some(format!("{:04}", y)),
+        (None, Some(m), Some(d)) => Some(format!("--{:02}{:02}", m, d)),
+        (None, Some(m), None) => Some(format!("--{:02}", m)),
+        (None, None, Some(d)) => Some(format!("---{:02}", d)),
+        (None, None, None) => None,
+    }
+}
+
+fn place_text(address: &Address) -> String {
+    [&address.city, &address.region, &address.country]
+        .into_iter()
+        .flatten()
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(date: &Date) -> (Option<i32>, Option<u8>, Option<u8>) {
+        (date.year, date.month, date.day)
+    }
+
+    #[test]
+    fn partial_dates_round_trip() {
+        let dates = [
+            (Some(1990), Some(6), Some(18), "19900618"),
+            (Some(1990), Some(6), None, "1990-06"),
+            (Some(1990), None, None, "1990"),
+            (None, Some(6), Some(18), "--0618"),
+            (None, Some(6), None, "--06"),
+            (None, None, Some(18), "---18"),
+        ];
+        for (year, month, day, text) in dates {
+            let date = Date { year, month, day, hour: None, minute: None, second: None };
+            assert_eq!(format_date(&date).as_deref(), Some(text));
+            assert_eq!(parse_date(text).as_ref().map(parts), Some((year, month, day)), "{}", text);
+        }
+    }
+
+    #[test]
+    fn extended_and_timed_dates_are_read() {
+        assert_eq!(parse_date("1990-06-18").as_ref().map(parts), Some((Some(1990), Some(6), Some(18))));
+        assert_eq!(parse_date("--06-18").as_ref().map(parts), Some((None, Some(6), Some(18))));
+        assert_eq!(parse_date("19900618T093000Z").as_ref().map(parts), Some((Some(1990), Some(6), Some(18))));
+        assert!(parse_date("---1").is_none());
+        assert!(parse_date("---32").is_none());
+    }
+}
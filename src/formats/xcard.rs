@@ -0,0 +1,180 @@
+use crate::contact::Contact;
+use crate::models::TuppData;
+use crate::name_format::NameFormat;
+use super::vcard::{self, Property};
+
+const NAMESPACE: &str = "urn:ietf:params:xml:ns:vcard-4.0";
+
+/// Write contacts as xCard (RFC 6351), the XML form of the vCard 4.0 export.
+pub fn write(contacts: &[&Contact], data: &TuppData, name: &NameFormat) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!("<vcards xmlns=\"{}\">\n", NAMESPACE));
+    for contact in contacts {
+        out.push_str("  <vcard>\n");
+        for property in vcard::to_properties(contact, data, name) {
+            // The version is implied by the namespace
+            if property.name != "VERSION" {
+                out.push_str(&format!("    {}\n", element(&property)));
+            }
+        }
+        out.push_str("  </vcard>\n");
+    }
+    out.push_str("</vcards>\n");
+    out
+}
+
+fn element(property: &Property) -> String {
+    let name = property.name.to_lowercase();
+    let mut out = format!("<{}>", name);
+
+    let mut names: Vec<&str> = Vec::new();
+    for (param, _) in &property.params {
+        if param != "VALUE" && !names.contains(&param.as_str()) {
+            names.push(param);
+        }
+    }
+    if !names.is_empty() {
+        out.push_str("<parameters>");
+        for param in names {
+            let value_type = if param == "PREF" { "integer" } else { "text" };
+            let values: String = property.param(param).map(|v| tag(value_type, v)).collect();
+            out.push_str(&format!("<{0}>{1}</{0}>", param.to_lowercase(), values));
+        }
+        out.push_str("</parameters>");
+    }
+
+    let value_type = property.value_type();
+    if property.is_structured() {
+        let parts: &[&str] = match property.name.as_str() {
+            "N" => &["surname", "given", "additional", "prefix", "suffix"],
+            "ADR" => &["pobox", "ext", "street", "locality", "region", "code", "country"],
+            _ => &["sex", "identity"],
+        };
+        let components = property.components();
+        for (i, part) in parts.iter().enumerate() {
+            out.push_str(&tag(part, components.get(i).map_or("", String::as_str)));
+        }
+    } else if property.is_list() {
+        for value in property.list() {
+            out.push_str(&tag(&value_type, &value));
+        }
+    } else {
+        out.push_str(&tag(&value_type, &property.text()));
+    }
+
+    out.push_str(&format!("</{}>", name));
+    out
+}
+
+fn tag(name: &str, text: &str) -> String {
+    if text.is_empty() {
+        format!("<{}/>", name)
+    } else {
+        format!("<{0}>{1}</{0}>", name, escape(text))
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write;
+    use crate::commands::serve::negotiate;
+    use crate::formats::jcard::tests::{assert_round_trip, sample_data};
+    use crate::formats::vcard::{self, Property};
+    use crate::formats::ExportFormat;
+    use crate::name_format::FULL_NAME;
+
+    /// The elements at the top level of an XML fragment, as names and inner
+    /// XML. Enough for the elements written here, which have no attributes.
+    fn elements(xml: &str) -> Vec<(String, String)> {
+        let mut found = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            let end = start + rest[start..].find('>').unwrap();
+            let tag = &rest[start + 1..end];
+            if let Some(name) = tag.strip_suffix('/') {
+                found.push((name.to_string(), String::new()));
+                rest = &rest[end + 1..];
+                continue;
+            }
+            let close = format!("</{}>", tag);
+            let inner_end = end + 1 + rest[end + 1..].find(&close).unwrap();
+            found.push((tag.to_string(), rest[end + 1..inner_end].to_string()));
+            rest = &rest[inner_end + close.len()..];
+        }
+        found
+    }
+
+    fn unescape(text: &str) -> String {
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&")
+    }
+
+    /// Parse the first xCard of a document back into vCard properties with
+    /// escaped values.
+    fn parse(xml: &str) -> Vec<Property> {
+        let start = xml.find("<vcard>").unwrap() + "<vcard>".len();
+        let end = xml.find("</vcard>").unwrap();
+
+        elements(&xml[start..end]).into_iter().map(|(name, inner)| {
+            let mut property = Property { name: name.to_uppercase(), params: Vec::new(), value: String::new() };
+            let mut values = Vec::new();
+            for (child, content) in elements(&inner) {
+                if child != "parameters" {
+                    values.push(vcard::escape(&unescape(&content)));
+                    continue;
+                }
+                for (param, typed) in elements(&content) {
+                    for (_, value) in elements(&typed) {
+                        property.params.push((param.to_uppercase(), unescape(&value)));
+                    }
+                }
+            }
+            property.value = values.join(if property.is_structured() { ";" } else { "," });
+            property
+        }).collect()
+    }
+
+    #[test]
+    fn xcard_round_trip() {
+        let data = sample_data();
+        let xml = write(&[&data.contacts[0]], &data, &FULL_NAME);
+        assert!(xml.contains("<vcards xmlns=\"urn:ietf:params:xml:ns:vcard-4.0\">"));
+        let properties = parse(&xml);
+
+        let names: Vec<&str> = properties.iter().map(|p| p.name.as_str()).collect();
+        let expected: Vec<String> = vcard::to_properties(&data.contacts[0], &data, &FULL_NAME)
+            .into_iter()
+            .map(|p| p.name)
+            .filter(|name| name != "VERSION")
+            .collect();
+        assert_eq!(names, expected);
+        assert_round_trip(&properties, &data);
+    }
+
+    #[test]
+    fn xcard_escapes_markup() {
+        let mut data = sample_data();
+        data.contacts[0].identity.first_name = Some("<Pierre> & \"Pete\"".to_string());
+        let xml = write(&[&data.contacts[0]], &data, &FULL_NAME);
+        assert!(xml.contains("<given>&lt;Pierre&gt; &amp; &quot;Pete&quot;</given>"));
+        let properties = parse(&xml);
+        let n = properties.iter().find(|p| p.name == "N").unwrap();
+        assert_eq!(n.components()[1], "<Pierre> & \"Pete\"");
+    }
+
+    #[test]
+    fn negotiate_picks_xcard() {
+        let format = |accept| negotiate(Some(accept)).map(|(format, _)| format);
+        assert_eq!(format("application/vcard+xml"), Some(ExportFormat::Xcard));
+        assert_eq!(format("application/json;q=0.5, application/vcard+xml"), Some(ExportFormat::Xcard));
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{ Deserialize, Serialize };
 use std::hash::{Hash, Hasher};
 use uuid::Uuid;
+use crate::matching::{self, MatchKind};
+use crate::sanitize::fold;
 
 #[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
 pub struct Group {
@@ -36,7 +38,6 @@ impl Group {
         }
     }
 
-    #[allow(dead_code)]
     pub fn contains_id_recursive(groups: &[Group], id: &Uuid) -> bool {
         for group in groups {
             if &group.identifier == id {
@@ -62,20 +63,43 @@ impl Group {
         false
     }
 
+    /// Find the group whose name best matches the text, ignoring case and
+    /// accents and tolerating typos. Ties go to the first group in tree order.
     pub fn find_best_match<'a>(groups: &'a [Group], text: &str) -> Option<&'a Group> {
         if let Ok(id) = Uuid::parse_str(text) {
              return Self::find_group_by_id_recursive(groups, &id);
         }
-        
-        for group in groups {
-            if group.name.to_lowercase().contains(&text.to_lowercase()) {
-                return Some(group);
-            }
-            if let Some(found) = Self::find_best_match(&group.subgroups, text) {
-                return Some(found);
+
+        let query = fold(text);
+        let threshold = crate::config::config().fuzzy_threshold;
+        let mut best: Option<(MatchKind, &Group)> = None;
+        Self::visit_recursive(groups, &mut |group| {
+            if let Some(kind) = matching::match_kind(&query, &fold(&group.name), threshold) {
+                if best.is_none_or(|(best_kind, _)| kind > best_kind) {
+                    best = Some((kind, group));
+                }
             }
+        });
+        best.map(|(_, group)| group)
+    }
+
+    fn visit_recursive<'a>(groups: &'a [Group], visit: &mut dyn FnMut(&'a Group)) {
+        for group in groups {
+            visit(group);
+            Self::visit_recursive(&group.subgroups, visit);
         }
-        None
+    }
+
+    /// Find the first group, in tree order, with this name, ignoring case and accents.
+    pub fn find_group_by_exact_name<'a>(groups: &'a [Group], name: &str) -> Option<&'a Group> {
+        let name = fold(name);
+        let mut found = None;
+        Self::visit_recursive(groups, &mut |group| {
+            if found.is_none() && fold(&group.name) == name {
+                found = Some(group);
+            }
+        });
+        found
     }
 
     pub fn find_group_by_id_recursive<'a>(groups: &'a [Group], id: &Uuid) -> Option<&'a Group> {
@@ -122,15 +146,9 @@ impl Group {
         self.subgroups.retain(|g| &g.identifier != child_id);
         
         if self.subgroups.len() < initial_len {
-             return Ok(format!(
-                 "Subgroup removed from {}",
-                 self.name
-             ));
+            Ok(format!("Subgroup removed from {}", self.name))
         } else {
-            return Err(format!(
-                 "Subgroup not found in {}",
-                 self.name
-             ));
-        }   
+            Err(format!("Subgroup not found in {}", self.name))
+        }
     }
 }
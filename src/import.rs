@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use uuid::Uuid;
+use crate::contact::Contact;
+use crate::group::Group;
+use crate::merge::{self, Side};
+use crate::models::{Source, TuppData};
+use crate::sanitize::fold;
+use crate::name_format::FIRST_LAST;
+use crate::error::TuppError;
+
+/// A place contacts can be imported from: a file format, or the files
+/// another address book keeps.
+pub trait Importer {
+    /// The name recorded with the source identifiers of the contacts.
+    fn name(&self) -> &'static str;
+
+    /// Read the contacts of a file or directory.
+    fn read(&self, path: &Path, default_country_code: u16) -> Result<Fragment, TuppError>;
+}
+
+/// The contacts read by an importer, before they are added to the data.
+#[derive(Default)]
+pub struct Fragment {
+    pub contacts: Vec<Imported>,
+    pub report: Report,
+}
+
+/// How the fields of a source were imported: for each source field, the
+/// contact field it went to, if any, and how many values it had.
+#[derive(Default)]
+pub struct Report {
+    fields: BTreeMap<String, (Option<String>, usize)>,
+}
+
+impl Report {
+    pub fn record(&mut self, field: &str, target: Option<&str>) {
+        self.fields
+            .entry(field.to_string())
+            .or_insert_with(|| (target.map(str::to_string), 0))
+            .1 += 1;
+    }
+
+    pub fn extend(&mut self, other: Report) {
+        for (field, (target, count)) in other.fields {
+            self.fields.entry(field).or_insert((target, 0)).1 += count;
+        }
+    }
+
+    pub fn print(&self) {
+        if self.fields.is_empty() {
+            return;
+        }
+        let width = self.fields.keys().map(|f| f.chars().count()).max().unwrap_or(0);
+        println!("Field mapping:");
+        for (field, (target, count)) in &self.fields {
+            let target = target.as_deref().map_or("not imported".to_string(), |t| format!("-> {}", t));
+            println!("\t{:width$}  {:<20} {}", field, target, count, width = width);
+        }
+    }
+}
+
+/// Read a file as text, replacing invalid UTF-8.
+pub fn read_text(path: &Path) -> Result<String, TuppError> {
+    let bytes = std::fs::read(path).map_err(TuppError::Io)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// What to do with an imported contact that looks like an existing one.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OnDuplicate {
+    /// Drop the imported contact.
+    Skip,
+    /// Merge the imported contact into the existing one, keeping existing values on conflict.
+    Merge,
+    /// Import the contact anyway.
+    Keep,
+}
+
+/// A contact read from another format.
+pub struct Imported {
+    pub contact: Contact,
+    /// Names of the groups of the contact, created when missing.
+    pub groups: Vec<String>,
+    /// Source fields that have no equivalent in a contact.
+    pub unmapped: Vec<String>,
+    /// The identifier of the contact in the source, if it has one.
+    pub source_uid: Option<String>,
+}
+
+impl Imported {
+    pub fn new(contact: Contact, groups: Vec<String>, unmapped: Vec<String>) -> Self {
+        Self { contact, groups, unmapped, source_uid: None }
+    }
+
+    /// The identifier of the contact in the source or, for sources without
+    /// identifiers, a key made of its name and first email or phone.
+    pub fn source_key(&self) -> String {
+        if let Some(uid) = &self.source_uid {
+            return uid.clone();
+        }
+        let contact = &self.contact;
+        let reach = contact.emails.iter().flatten().find_map(|e| e.address.as_deref().map(str::to_lowercase))
+            .or_else(|| contact.phones.iter().flatten().next().map(|p| format!("+{}{}", p.country_code, p.number)))
+            .unwrap_or_default();
+        format!("{}|{}", fold(&contact.format_name(&FIRST_LAST)), reach)
+    }
+}
+
+/// What happened to an imported contact.
+pub enum Outcome {
+    Added(Uuid),
+    Merged { into: Uuid },
+    Skipped { duplicate_of: Uuid, reasons: Vec<String> },
+    /// Imported before from the same source, and changed there since.
+    Updated { into: Uuid },
+    /// Imported before from the same source, and unchanged.
+    Unchanged,
+}
+
+/// Add imported contacts to the data, one by one, so that duplicates within
+/// the imported contacts are detected as well.
+///
+/// A contact imported before by the same importer, as recorded in its
+/// sources, is updated: values from the source win over conflicting ones,
+/// and emails, phones and groups are merged, so that what was added in
+/// tupp is kept. Otherwise, a contact is a duplicate when it has the
+/// identifier of an existing contact, or when it scores at least
+/// `min_score` against one (see `dedupe`).
+pub fn import_contacts(
+    data: &mut TuppData,
+    imported: Vec<Imported>,
+    importer: &str,
+    on_duplicate: OnDuplicate,
+    min_score: f64,
+) -> Result<Vec<(String, Outcome)>, TuppError> {
+    let mut outcomes = Vec::new();
+
+    for imported in imported {
+        let source = Source { importer: importer.to_string(), uid: imported.source_key() };
+        let Imported { mut contact, groups, .. } = imported;
+        contact.sources = Some(vec![source.clone()]);
+        let name = contact.format_name(&FIRST_LAST);
+
+        let known = data.contacts.iter()
+            .find(|c| c.sources.iter().flatten().any(|s| *s == source))
+            .map(|c| c.identifier);
+        if let Some(existing) = known {
+            if !groups.is_empty() {
+                contact.groups = Some(groups.iter().map(|name| group_id(data, name)).collect());
+            }
+            contact.identifier = Uuid::new_v4();
+            let id = contact.identifier;
+            let before = snapshot(data, existing);
+            data.contacts.push(contact);
+            merge::merge_contacts(data, existing, id, &mut |_, _, _| Side::B)?;
+            if snapshot(data, existing) == before {
+                outcomes.push((name, Outcome::Unchanged));
+            } else {
+                if let Some(kept) = data.contacts.iter_mut().find(|c| c.identifier == existing) {
+                    kept.touch();
+                }
+                outcomes.push((name, Outcome::Updated { into: existing }));
+            }
+            continue;
+        }
+
+        let duplicate = find_duplicate(data, &contact, min_score);
+
+        if let (Some((existing, reasons)), OnDuplicate::Skip) = (&duplicate, on_duplicate) {
+            outcomes.push((name, Outcome::Skipped { duplicate_of: *existing, reasons: reasons.clone() }));
+            continue;
+        }
+
+        if data.contacts.iter().any(|c| c.identifier == contact.identifier) {
+            contact.identifier = Uuid::new_v4();
+        }
+        if !groups.is_empty() {
+            contact.groups = Some(groups.iter().map(|name| group_id(data, name)).collect());
+        }
+
+        let id = contact.identifier;
+        data.contacts.push(contact);
+
+        match duplicate {
+            Some((existing, _)) if on_duplicate == OnDuplicate::Merge => {
+                merge::merge_contacts(data, existing, id, &mut |_, _, _| Side::A)?;
+                if let Some(kept) = data.contacts.iter_mut().find(|c| c.identifier == existing) {
+                    kept.touch();
+                }
+                outcomes.push((name, Outcome::Merged { into: existing }));
+            },
+            _ => outcomes.push((name, Outcome::Added(id))),
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// A contact as JSON, without its modification time.
+fn snapshot(data: &TuppData, id: Uuid) -> Option<serde_json::Value> {
+    let contact = data.contacts.iter().find(|c| c.identifier == id)?;
+    let mut value = serde_json::to_value(contact).ok()?;
+    value.as_object_mut()?.remove("updated");
+    Some(value)
+}
+
+fn find_duplicate(data: &TuppData, contact: &Contact, min_score: f64) -> Option<(Uuid, Vec<String>)> {
+    if data.contacts.iter().any(|c| c.identifier == contact.identifier) {
+        return Some((contact.identifier, vec!["same identifier".to_string()]));
+    }
+
+    data.contacts
+        .iter()
+        .map(|existing| (existing.identifier, crate::dedupe::score(existing, contact)))
+        .filter(|(_, (score, reasons))| *score >= min_score && !reasons.is_empty())
+        .max_by(|(_, (a, _)), (_, (b, _))| a.total_cmp(b))
+        .map(|(id, (_, reasons))| (id, reasons))
+}
+
+/// The identifier of the group with this name, creating it at the top level if needed.
+fn group_id(data: &mut TuppData, name: &str) -> Uuid {
+    if let Some(group) = Group::find_group_by_exact_name(&data.groups, name) {
+        return group.identifier;
+    }
+    let group = Group::new(name.to_string());
+    let id = group.identifier;
+    data.groups.push(group);
+    id
+}
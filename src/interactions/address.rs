@@ -147,6 +147,8 @@ pub fn create_address_interactive(existing_labels: &[String]) -> Address {
     }
 }
 
+// One argument per field of an address, as given on the command line
+#[allow(clippy::too_many_arguments)]
 pub fn create_address(
     label: Option<String>,
     country: Option<String>,
@@ -172,6 +174,8 @@ pub fn create_address(
     }
 }
 
+// One argument per field of an address, as given on the command line
+#[allow(clippy::too_many_arguments)]
 pub fn add_address_to_contact(
     contact: &mut crate::contact::Contact,
     label: Option<String>,
@@ -162,6 +162,7 @@ pub fn create_contact_interactive(
             is_alive: true,
             death_date: None,
             death_location: None,
+            anniversary: None,
         },
         emails,
         phones,
@@ -169,5 +170,8 @@ pub fn create_contact_interactive(
         socials,
         groups: None,
         links: None,
+        sources: None,
+        created: Some(chrono::Utc::now()),
+        updated: None,
     })
 }
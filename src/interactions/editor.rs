@@ -0,0 +1,126 @@
+use dialoguer::{Confirm, Editor};
+use serde_json::Value;
+use crate::contact::Contact;
+use crate::models::TuppData;
+use crate::error::{Result, TuppError};
+use crate::{diff, validation};
+
+const HEADER: &str = "\
+# Edit the contact below, then save and close the editor to apply the changes.
+# Lines starting with '#' are ignored. An empty file aborts the edit.
+";
+
+/// Prefix of the lines explaining why the previous edit was refused.
+const ERROR_PREFIX: &str = "# ERROR: ";
+
+/// Open the contact at `index` in `$VISUAL`/`$EDITOR` as TOML, and return the
+/// edited contact once it parses and validates. Invalid input re-opens the
+/// editor with the error prepended, so that nothing typed is lost.
+/// Returns `None` if the edit was aborted or nothing changed.
+pub fn edit_contact_in_editor(data: &TuppData, index: usize) -> Result<Option<Contact>> {
+    let original = serde_json::to_value(&data.contacts[index])?;
+    let mut text = format!("{}\n{}", HEADER, to_toml(&original)?);
+
+    loop {
+        let edited = match Editor::new().extension(".toml").edit(&text)? {
+            Some(edited) => edited,
+            None => return Ok(None),
+        };
+
+        let body = strip_header(&edited);
+        if body.lines().all(|l| l.trim().is_empty() || l.trim_start().starts_with('#')) {
+            println!("Empty contact, edit aborted.");
+            return Ok(None);
+        }
+
+        match parse_contact(data, index, &original, &body) {
+            Ok(edited_value) => {
+                let changes = diff::changes(&original, &edited_value);
+                if changes.is_empty() {
+                    println!("No changes.");
+                    return Ok(None);
+                }
+                for change in &changes {
+                    println!("{}", change);
+                }
+                return Ok(Some(serde_json::from_value(edited_value)?));
+            },
+            Err(e) => {
+                println!("{}", e);
+                if !Confirm::new()
+                    .with_prompt("Re-open the editor to fix it?")
+                    .default(true)
+                    .interact()?
+                {
+                    return Ok(None);
+                }
+                let error_lines: String = e
+                    .to_string()
+                    .lines()
+                    .map(|l| format!("{}{}\n", ERROR_PREFIX, l))
+                    .collect();
+                text = format!("{}{}\n{}", HEADER, error_lines, body);
+            },
+        }
+    }
+}
+
+fn parse_contact(data: &TuppData, index: usize, original: &Value, body: &str) -> Result<Value> {
+    let parsed: toml::Value = toml::from_str(body)
+        .map_err(|e| TuppError::Validation(format!("Invalid contact text: {}", e)))?;
+    let edited = serde_json::to_value(parsed)?;
+
+    if edited.get("identifier") != original.get("identifier") {
+        return Err(TuppError::Validation("The contact identifier cannot be changed".to_string()));
+    }
+
+    // Validate the edited contact in place, against the whole data file
+    let mut whole = serde_json::to_value(data)?;
+    whole["contacts"][index] = edited.clone();
+    validation::validate_json(&whole)?;
+
+    Ok(edited)
+}
+
+fn to_toml(value: &Value) -> Result<String> {
+    toml::to_string_pretty(&without_nulls(value))
+        .map_err(|e| TuppError::Other(format!("Cannot serialize contact: {}", e)))
+}
+
+/// TOML has no null, so absent optional fields are simply left out.
+fn without_nulls(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k.clone(), without_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(without_nulls).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Remove the header and error lines added at the top of the text, keeping
+/// the comments of the user, which TOML ignores anyway.
+fn strip_header(text: &str) -> String {
+    let mut lines = text.lines().peekable();
+    while lines
+        .next_if(|l| l.trim().is_empty() || HEADER.lines().any(|h| h == *l) || l.starts_with(ERROR_PREFIX))
+        .is_some()
+    {}
+    lines.map(|l| format!("{}\n", l)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_header_keeps_the_comments_of_the_user() {
+        let body = "identifier = \"x\"\n# kept\n[identity]\nfirst_name = \"# not a comment\"\n";
+        let text = format!("{}{}Missing field\n\n{}", HEADER, ERROR_PREFIX, body);
+        assert_eq!(strip_header(&text), body);
+        assert_eq!(strip_header(&format!("{}\n{}", HEADER, body)), body);
+    }
+}
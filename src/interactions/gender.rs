@@ -1,12 +1,18 @@
 use dialoguer::Select;
 use crate::models::Gender;
 
-pub fn create_gender_interactive() -> Gender {
+/// Ask for a gender, with `current` selected by default.
+pub fn create_gender_interactive(current: Option<&Gender>) -> Gender {
     let options = vec!["male", "female", "non-binary"];
-    
+    let default = match current {
+        Some(Gender::Female) => 1,
+        Some(Gender::NonBinary) => 2,
+        _ => 0,
+    };
+
     let selection = Select::new()
         .with_prompt("Select gender")
-        .default(0)
+        .default(default)
         .items(&options)
         .interact()
         .unwrap();
@@ -34,5 +40,5 @@ pub fn add_gender_to_contact(contact: &mut crate::contact::Contact, gender: Opti
             println!("Invalid gender. Valid values are: male, female, non-binary.");
         }
     }
-    contact.identity.gender = Some(create_gender_interactive());
+    contact.identity.gender = Some(create_gender_interactive(contact.identity.gender.as_ref()));
 }
@@ -0,0 +1,13 @@
+use dialoguer::Select;
+use crate::merge::Side;
+
+pub fn resolve_conflict_interactive(field: &str, a: &str, b: &str) -> Side {
+    let selection = Select::new()
+        .with_prompt(format!("Conflicting values for {}", field))
+        .items(&[format!("a: {}", a), format!("b: {}", b)])
+        .default(0)
+        .interact()
+        .unwrap();
+
+    if selection == 0 { Side::A } else { Side::B }
+}
@@ -15,3 +15,13 @@ pub use death::*;
 pub use gender::*;
 pub mod create;
 pub use create::*;
+pub mod editor;
+pub use editor::*;
+pub mod wizard;
+pub use wizard::*;
+pub mod remove;
+pub use remove::*;
+pub mod merge;
+pub use merge::*;
+pub mod select;
+pub use select::*;
@@ -0,0 +1,173 @@
+use dialoguer::Select;
+use crate::contact::Contact;
+use crate::group::Group;
+use crate::name_format::FIRST_LAST;
+
+fn select_index(prompt: &str, items: &[String]) -> usize {
+    Select::new()
+        .with_prompt(prompt)
+        .items(items)
+        .default(0)
+        .interact()
+        .unwrap()
+}
+
+pub fn remove_email_from_contact(contact: &mut Contact, label: Option<String>) -> bool {
+    let emails = match contact.emails.as_mut() {
+        Some(emails) if !emails.is_empty() => emails,
+        _ => {
+            println!("Error: This contact has no email.");
+            return false;
+        }
+    };
+
+    let index = if let Some(label) = label {
+        match emails.iter().position(|e| e.label.as_deref() == Some(label.as_str())) {
+            Some(index) => index,
+            None => {
+                println!("Error: No email labelled '{}' for this contact.", label);
+                return false;
+            }
+        }
+    } else {
+        let items: Vec<String> = emails.iter().map(|e| e.to_string()).collect();
+        select_index("Select the email to remove", &items)
+    };
+
+    emails.remove(index);
+    if emails.is_empty() {
+        contact.emails = None;
+    }
+    true
+}
+
+pub fn remove_phone_from_contact(contact: &mut Contact, label: Option<String>) -> bool {
+    let phones = match contact.phones.as_mut() {
+        Some(phones) if !phones.is_empty() => phones,
+        _ => {
+            println!("Error: This contact has no phone number.");
+            return false;
+        }
+    };
+
+    let index = if let Some(label) = label {
+        match phones.iter().position(|p| p.label.as_deref() == Some(label.as_str())) {
+            Some(index) => index,
+            None => {
+                println!("Error: No phone number labelled '{}' for this contact.", label);
+                return false;
+            }
+        }
+    } else {
+        let items: Vec<String> = phones.iter().map(|p| p.to_string()).collect();
+        select_index("Select the phone number to remove", &items)
+    };
+
+    phones.remove(index);
+    if phones.is_empty() {
+        contact.phones = None;
+    }
+    true
+}
+
+pub fn remove_social_from_contact(
+    contact: &mut Contact,
+    label: Option<String>,
+    network: Option<String>,
+    index: Option<usize>,
+) -> bool {
+    let socials = match contact.socials.as_mut() {
+        Some(socials) if !socials.is_empty() => socials,
+        _ => {
+            println!("Error: This contact has no social network.");
+            return false;
+        }
+    };
+
+    let position = if let Some(index) = index {
+        if index >= socials.len() {
+            println!("Error: No social network at index {} for this contact.", index);
+            return false;
+        }
+        index
+    } else if label.is_some() || network.is_some() {
+        let found = socials.iter().position(|s| {
+            label.as_ref().is_none_or(|l| s.label.as_deref() == Some(l.as_str()))
+                && network.as_ref().is_none_or(|n| s.network.eq_ignore_ascii_case(n))
+        });
+        match found {
+            Some(position) => position,
+            None => {
+                println!("Error: No matching social network for this contact.");
+                return false;
+            }
+        }
+    } else {
+        let items: Vec<String> = socials.iter().map(|s| s.to_string()).collect();
+        select_index("Select the social network to remove", &items)
+    };
+
+    socials.remove(position);
+    if socials.is_empty() {
+        contact.socials = None;
+    }
+    true
+}
+
+pub fn remove_group_from_contact(contact: &mut Contact, groups: &[Group], name_or_id: Option<String>) -> bool {
+    let contact_groups = match contact.groups.as_mut() {
+        Some(contact_groups) if !contact_groups.is_empty() => contact_groups,
+        _ => {
+            println!("Error: This contact is not in any group.");
+            return false;
+        }
+    };
+
+    let group_id = if let Some(name_or_id) = name_or_id {
+        match Group::find_best_match(groups, &name_or_id) {
+            Some(group) if contact_groups.contains(&group.identifier) => group.identifier,
+            Some(group) => {
+                println!("Error: Contact is not in group '{}'.", group.name);
+                return false;
+            }
+            None => {
+                println!("No group found matching '{}'.", name_or_id);
+                return false;
+            }
+        }
+    } else {
+        let ids: Vec<_> = contact_groups.iter().copied().collect();
+        let items: Vec<String> = ids
+            .iter()
+            .map(|id| {
+                Group::find_group_by_id_recursive(groups, id)
+                    .map(|g| g.name.clone())
+                    .unwrap_or_else(|| id.to_string())
+            })
+            .collect();
+        ids[select_index("Select the group to leave", &items)]
+    };
+
+    contact_groups.remove(&group_id);
+    if contact_groups.is_empty() {
+        contact.groups = None;
+    }
+    true
+}
+
+/// Pick one of the contact's links interactively, returning the target's identifier.
+pub fn select_link_target(contact: &Contact, contacts: &[Contact]) -> Option<uuid::Uuid> {
+    let links = contact.links.as_ref().filter(|l| !l.is_empty())?;
+    let items: Vec<String> = links
+        .iter()
+        .map(|link| {
+            let name = contacts
+                .iter()
+                .find(|c| c.identifier == link.target)
+                .map(|c| c.format_name(&FIRST_LAST))
+                .unwrap_or_else(|| link.target.to_string());
+            format!("{}: {}", link.relation, name)
+        })
+        .collect();
+    Some(links[select_index("Select the relationship to remove", &items)].target)
+}
@@ -0,0 +1,27 @@
+use dialoguer::Select;
+use crate::contact::{Contact, SearchResult};
+use crate::name_format::FIRST_LAST;
+use crate::error::Result;
+
+/// Ask which of the matching contacts was meant, best matches first. A
+/// single result is confirmed this way too when it is not an exact match.
+pub fn select_contact<'a>(results: &[SearchResult<'a>]) -> Result<&'a Contact> {
+    let items: Vec<String> = results
+        .iter()
+        .map(|r| format!(
+            "{} ({}, {} match on {})",
+            r.contact.format_name(&FIRST_LAST),
+            r.contact.identifier,
+            r.kind,
+            r.field
+        ))
+        .collect();
+
+    let selection = Select::new()
+        .with_prompt(if results.len() > 1 { "Several contacts match, select one" } else { "No exact match, select the contact" })
+        .items(&items)
+        .default(0)
+        .interact()?;
+
+    Ok(results[selection].contact)
+}
@@ -0,0 +1,245 @@
+use dialoguer::{Confirm, Input, Select};
+use crate::contact::Contact;
+use crate::models::Date;
+use crate::error::Result;
+
+#[derive(Clone, Copy)]
+enum Field {
+    Title,
+    FirstName,
+    MiddleName,
+    LastName,
+    PostNominal,
+    Gender,
+    Alive,
+    BirthDate,
+    BirthFirstName,
+    BirthMiddleName,
+    BirthLastName,
+    Anniversary,
+    DeathDate,
+    AddressLabel,
+    AddressNumber,
+    AddressStreet,
+    AddressPostCode,
+    AddressCity,
+    AddressRegion,
+    AddressCountry,
+    Email(usize),
+    Phone(usize),
+    Social(usize),
+    Done,
+}
+
+/// Let the user pick fields of a contact one at a time and edit them in place,
+/// starting from their current value. Clearing a value removes it.
+/// Returns whether any value was changed.
+pub fn edit_contact_wizard(contact: &mut Contact) -> Result<bool> {
+    let mut changed = false;
+
+    loop {
+        let fields = list_fields(contact);
+        let items: Vec<String> = fields.iter().map(|(_, label)| label.clone()).collect();
+
+        let selection = Select::new()
+            .with_prompt("Select a field to edit")
+            .items(&items)
+            .default(0)
+            .interact()?;
+
+        let field = fields[selection].0;
+        // Models have no equality, so the contact is compared as JSON
+        let before = serde_json::to_value(&*contact)?;
+        match field {
+            Field::Done => return Ok(changed),
+            Field::Gender => {
+                contact.identity.gender = Some(crate::interactions::gender::create_gender_interactive(contact.identity.gender.as_ref()));
+            },
+            Field::Alive => {
+                contact.identity.is_alive = Confirm::new()
+                    .with_prompt("Is this contact alive?")
+                    .default(contact.identity.is_alive)
+                    .interact()?;
+            },
+            Field::BirthDate | Field::Anniversary | Field::DeathDate => {
+                let date = match field {
+                    Field::BirthDate => &mut contact.identity.birth_date,
+                    Field::Anniversary => &mut contact.identity.anniversary,
+                    _ => &mut contact.identity.death_date,
+                };
+                let initial = date.as_ref().map(|d| d.to_string()).unwrap_or_default();
+                *date = loop {
+                    let input = prompt_value("Date (YYYY-MM-DD)", initial.clone())?;
+                    if input.is_empty() {
+                        break None;
+                    }
+                    match input.parse::<Date>() {
+                        Ok(d) => break Some(d),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                };
+            },
+            Field::Email(i) => {
+                let emails = contact.emails.as_mut().unwrap();
+                let input = prompt_value("Email Address", emails[i].address.clone().unwrap_or_default())?;
+                if input.is_empty() {
+                    emails.remove(i);
+                } else {
+                    emails[i].address = Some(input);
+                }
+            },
+            Field::Phone(i) => {
+                let phones = contact.phones.as_mut().unwrap();
+                let initial = format!("+{} {}", phones[i].country_code, phones[i].number);
+                loop {
+                    let input = prompt_value("Phone Number (+CC NUMBER)", initial.clone())?;
+                    if input.is_empty() {
+                        phones.remove(i);
+                        break;
+                    }
+                    match parse_phone(&input) {
+                        Some((country_code, number)) => {
+                            phones[i].country_code = country_code;
+                            phones[i].number = number;
+                            break;
+                        },
+                        None => println!("Error: '{}' is not a valid phone number.", input),
+                    }
+                }
+            },
+            Field::Social(i) => {
+                let socials = contact.socials.as_mut().unwrap();
+                let input = prompt_value("Username/Handle", socials[i].username.clone().unwrap_or_default())?;
+                if input.is_empty() {
+                    socials.remove(i);
+                } else {
+                    socials[i].username = Some(input);
+                }
+            },
+            text_field => {
+                let (prompt, value) = text_value(contact, text_field);
+                let input = prompt_value(prompt, value.clone().unwrap_or_default())?;
+                *value = if input.is_empty() { None } else { Some(input) };
+            },
+        }
+
+        // Drop emptied collections so the stored contact stays minimal
+        if contact.emails.as_ref().is_some_and(|v| v.is_empty()) {
+            contact.emails = None;
+        }
+        if contact.phones.as_ref().is_some_and(|v| v.is_empty()) {
+            contact.phones = None;
+        }
+        if contact.socials.as_ref().is_some_and(|v| v.is_empty()) {
+            contact.socials = None;
+        }
+        changed |= serde_json::to_value(&*contact)? != before;
+    }
+}
+
+fn list_fields(contact: &Contact) -> Vec<(Field, String)> {
+    let identity = &contact.identity;
+    let show = |value: &Option<String>| value.clone().unwrap_or_default();
+
+    let mut fields = vec![
+        (Field::Title, format!("Title: {}", show(&identity.title))),
+        (Field::FirstName, format!("First Name: {}", show(&identity.first_name))),
+        (Field::MiddleName, format!("Middle Name: {}", show(&identity.middle_name))),
+        (Field::LastName, format!("Last Name: {}", show(&identity.last_name))),
+        (Field::PostNominal, format!("Post-nominal: {}", show(&identity.post_nominal))),
+        (Field::Gender, format!(
+            "Gender: {}",
+            match identity.gender {
+                Some(crate::models::Gender::Male) => "Male",
+                Some(crate::models::Gender::Female) => "Female",
+                Some(crate::models::Gender::NonBinary) => "Non-binary",
+                None => "",
+            }
+        )),
+        (Field::Alive, format!("Alive: {}", if identity.is_alive { "Yes" } else { "No" })),
+        (Field::BirthDate, format!(
+            "Birth Date: {}",
+            identity.birth_date.as_ref().map(|d| d.to_string()).unwrap_or_default()
+        )),
+        (Field::BirthFirstName, format!("Birth First Name: {}", show(&identity.birth_first_name))),
+        (Field::BirthMiddleName, format!("Birth Middle Name: {}", show(&identity.birth_middle_name))),
+        (Field::BirthLastName, format!("Birth Last Name: {}", show(&identity.birth_last_name))),
+        (Field::Anniversary, format!(
+            "Anniversary: {}",
+            identity.anniversary.as_ref().map(|d| d.to_string()).unwrap_or_default()
+        )),
+    ];
+
+    if !identity.is_alive {
+        fields.push((Field::DeathDate, format!(
+            "Death Date: {}",
+            identity.death_date.as_ref().map(|d| d.to_string()).unwrap_or_default()
+        )));
+    }
+
+    if let Some(address) = &contact.address {
+        fields.push((Field::AddressLabel, format!("Address Label: {}", show(&address.label))));
+        fields.push((Field::AddressNumber, format!("Address Number: {}", show(&address.number))));
+        fields.push((Field::AddressStreet, format!("Address Street: {}", show(&address.street))));
+        fields.push((Field::AddressPostCode, format!("Address Postal Code: {}", show(&address.post_code))));
+        fields.push((Field::AddressCity, format!("Address City: {}", show(&address.city))));
+        fields.push((Field::AddressRegion, format!("Address Region: {}", show(&address.region))));
+        fields.push((Field::AddressCountry, format!("Address Country: {}", show(&address.country))));
+    }
+
+    for (i, email) in contact.emails.iter().flatten().enumerate() {
+        fields.push((Field::Email(i), format!("Email {}", email)));
+    }
+    for (i, phone) in contact.phones.iter().flatten().enumerate() {
+        fields.push((Field::Phone(i), format!("Phone {}", phone)));
+    }
+    for (i, social) in contact.socials.iter().flatten().enumerate() {
+        fields.push((Field::Social(i), format!("Social {}", social)));
+    }
+
+    fields.push((Field::Done, "Done".to_string()));
+    fields
+}
+
+fn text_value(contact: &mut Contact, field: Field) -> (&'static str, &mut Option<String>) {
+    let identity = &mut contact.identity;
+    match field {
+        Field::Title => ("Title", &mut identity.title),
+        Field::FirstName => ("First Name", &mut identity.first_name),
+        Field::MiddleName => ("Middle Name", &mut identity.middle_name),
+        Field::LastName => ("Last Name", &mut identity.last_name),
+        Field::PostNominal => ("Post-nominal", &mut identity.post_nominal),
+        Field::BirthFirstName => ("Birth first name", &mut identity.birth_first_name),
+        Field::BirthMiddleName => ("Birth middle name", &mut identity.birth_middle_name),
+        Field::BirthLastName => ("Birth last name", &mut identity.birth_last_name),
+        address_field => {
+            let address = contact.address.as_mut().unwrap();
+            match address_field {
+                Field::AddressLabel => ("Address Label", &mut address.label),
+                Field::AddressNumber => ("Number", &mut address.number),
+                Field::AddressStreet => ("Street", &mut address.street),
+                Field::AddressPostCode => ("Postal Code", &mut address.post_code),
+                Field::AddressCity => ("City", &mut address.city),
+                Field::AddressRegion => ("Region/State", &mut address.region),
+                Field::AddressCountry => ("Country", &mut address.country),
+                _ => unreachable!(),
+            }
+        },
+    }
+}
+
+fn prompt_value(prompt: &str, initial: String) -> Result<String> {
+    let input: String = Input::new()
+        .with_prompt(format!("{} (empty to remove)", prompt))
+        .with_initial_text(initial)
+        .allow_empty(true)
+        .interact_text()?;
+    Ok(input.trim().to_string())
+}
+
+fn parse_phone(input: &str) -> Option<(u16, u64)> {
+    let mut parts = input.trim_start_matches('+').splitn(2, char::is_whitespace);
+    let country_code = parts.next()?.parse().ok()?;
+    let number: String = parts.next()?.chars().filter(|c| !c.is_whitespace()).collect();
+    Some((country_code, number.parse().ok()?))
+}
@@ -0,0 +1,240 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// An address of a mail header, with its display name.
+#[derive(Debug, PartialEq)]
+pub struct Mailbox {
+    pub name: Option<String>,
+    pub address: String,
+}
+
+/// The unfolded headers of an RFC 5322 message, up to the first blank line.
+pub fn headers(message: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in message.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+/// The mailboxes of the address headers of a message with one of `names`,
+/// such as `From` and `Cc`, in order and without repeated addresses.
+pub fn mailboxes(message: &str, names: &[&str]) -> Vec<Mailbox> {
+    let mut mailboxes: Vec<Mailbox> = Vec::new();
+    for (name, value) in headers(message) {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+            continue;
+        }
+        for mailbox in parse_address_list(&value) {
+            if !mailboxes.iter().any(|m| m.address.eq_ignore_ascii_case(&mailbox.address)) {
+                mailboxes.push(mailbox);
+            }
+        }
+    }
+    mailboxes
+}
+
+/// Parse an address list such as `"Dupont, Jean" <jean@example.org>,
+/// marie@example.org (Marie Curie), Team: a@example.org;`.
+pub fn parse_address_list(value: &str) -> Vec<Mailbox> {
+    split_outside_quotes(value, ',')
+        .iter()
+        .filter_map(|part| parse_mailbox(part))
+        .collect()
+}
+
+fn parse_mailbox(part: &str) -> Option<Mailbox> {
+    let mut part = part.trim().trim_end_matches(';').trim();
+
+    // Group syntax: "Team: a@example.org, b@example.org;"
+    if let Some(colon) = find_outside_quotes(part, ':') {
+        if part.find('<').is_none_or(|angle| colon < angle) {
+            part = part[colon + 1..].trim();
+        }
+    }
+
+    let (name, address) = match (part.rfind('<'), part.rfind('>')) {
+        (Some(start), Some(end)) if start < end => (part[..start].trim().to_string(), part[start + 1..end].trim().to_string()),
+        _ => match (part.find('('), part.rfind(')')) {
+            (Some(start), Some(end)) if start < end => (part[start + 1..end].trim().to_string(), part[..start].trim().to_string()),
+            _ => (String::new(), part.to_string()),
+        },
+    };
+    if !address.contains('@') {
+        return None;
+    }
+
+    let name = decode_words(name.trim_matches('"').replace("\\\"", "\"").trim());
+    Some(Mailbox {
+        name: Some(name).filter(|n| !n.is_empty() && !n.eq_ignore_ascii_case(&address)),
+        address,
+    })
+}
+
+/// Split on a separator, except inside quotes, angle brackets and comments.
+fn split_outside_quotes(value: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let (mut quoted, mut angle, mut comment, mut escaped) = (false, false, 0, false);
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            '(' if !quoted => comment += 1,
+            ')' if !quoted && comment > 0 => comment -= 1,
+            c if c == separator && !quoted && !angle && comment == 0 => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            },
+            _ => {},
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+fn find_outside_quotes(value: &str, target: char) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == target && !quoted => return Some(i),
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Decode the encoded words of RFC 2047, as in `=?UTF-8?B?SMOpbMOobmU=?=`
+/// or `=?ISO-8859-1?Q?H=E9l=E8ne?=`. Whitespace between encoded words is dropped,
+/// and words in charsets other than UTF-8 and Latin-1 are kept as they are.
+pub fn decode_words(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    let mut previous_encoded = false;
+
+    while let Some(start) = rest.find("=?") {
+        // Skip the charset and the encoding before looking for the end, as
+        // the text itself may start with "=", as in "=?UTF-8?Q?=3D?="
+        let word = &rest[start + 2..];
+        let decoded = word
+            .match_indices('?')
+            .nth(1)
+            .and_then(|(text_start, _)| Some(text_start + 1 + word[text_start + 1..].find("?=")?))
+            .and_then(|end| decode_word(&word[..end]).map(|d| (d, start + 2 + end + 2)));
+        match decoded {
+            Some((decoded, end)) => {
+                let between = &rest[..start];
+                if !(previous_encoded && between.trim().is_empty()) {
+                    out.push_str(between);
+                }
+                out.push_str(&decoded);
+                rest = &rest[end..];
+                previous_encoded = true;
+            },
+            None => {
+                out.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+                previous_encoded = false;
+            },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_word(word: &str) -> Option<String> {
+    let mut parts = word.splitn(3, '?');
+    let charset = parts.next()?.split('*').next()?.to_lowercase();
+    let encoding = parts.next()?.to_lowercase();
+    let text = parts.next()?;
+
+    let bytes = match encoding.as_str() {
+        "b" => STANDARD.decode(text).ok()?,
+        "q" => {
+            let mut bytes = Vec::new();
+            let mut input = text.bytes();
+            while let Some(b) = input.next() {
+                match b {
+                    b'_' => bytes.push(b' '),
+                    b'=' => {
+                        let hex = [input.next()?, input.next()?];
+                        bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                    },
+                    b => bytes.push(b),
+                }
+            }
+            bytes
+        },
+        _ => return None,
+    };
+
+    // Other charsets are left encoded rather than decoded into garbage
+    match charset.as_str() {
+        "utf-8" | "utf8" => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        "iso-8859-1" | "latin1" | "us-ascii" => Some(bytes.iter().map(|&b| b as char).collect()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mailbox(name: Option<&str>, address: &str) -> Mailbox {
+        Mailbox { name: name.map(str::to_string), address: address.to_string() }
+    }
+
+    #[test]
+    fn address_lists_are_split_outside_quotes_and_comments() {
+        assert_eq!(parse_address_list("\"Dupont, Jean\" <jean@example.org>, marie@example.org"), [
+            mailbox(Some("Dupont, Jean"), "jean@example.org"),
+            mailbox(None, "marie@example.org"),
+        ]);
+        assert_eq!(parse_address_list("marie@example.org (Curie, Marie)"), [
+            mailbox(Some("Curie, Marie"), "marie@example.org"),
+        ]);
+        assert_eq!(parse_address_list("Team: a@example.org, Bob <b@example.org>;, undisclosed-recipients:;"), [
+            mailbox(None, "a@example.org"),
+            mailbox(Some("Bob"), "b@example.org"),
+        ]);
+        assert_eq!(parse_address_list("=?UTF-8?Q?H=C3=A9l=C3=A8ne?= <helene@example.org>"), [
+            mailbox(Some("Hélène"), "helene@example.org"),
+        ]);
+    }
+
+    #[test]
+    fn encoded_words_are_decoded() {
+        assert_eq!(decode_words("=?UTF-8?B?SMOpbMOobmU=?="), "Hélène");
+        assert_eq!(decode_words("=?ISO-8859-1?Q?H=E9l=E8ne_Dupont?="), "Hélène Dupont");
+        assert_eq!(decode_words("=?UTF-8?Q?=3D?="), "=");
+        assert_eq!(decode_words("Dr =?UTF-8?Q?Jos=C3=A9?= Garc\u{ed}a"), "Dr José García");
+    }
+
+    #[test]
+    fn space_between_adjacent_encoded_words_is_dropped() {
+        assert_eq!(decode_words("=?UTF-8?B?SMOp?= =?UTF-8?Q?l=C3=A8ne?="), "Hélène");
+        assert_eq!(decode_words("=?UTF-8?Q?Jean?=\r\n =?UTF-8?B?LU1pY2hlbA==?="), "Jean-Michel");
+    }
+
+    #[test]
+    fn unsupported_charsets_are_left_encoded() {
+        assert_eq!(decode_words("=?KOI8-R?B?8NLJ18XU?="), "=?KOI8-R?B?8NLJ18XU?=");
+        assert_eq!(decode_words("=?X-UNKNOWN?Q?a?= =?UTF-8?Q?b?="), "=?X-UNKNOWN?Q?a?= b");
+    }
+}
@@ -8,6 +8,21 @@ mod sanitize;
 mod interactions;
 mod commands;
 mod validation;
+mod diff;
+mod merge;
+mod dedupe;
+mod matching;
+mod config;
+mod query;
+mod sort;
+mod output;
+mod card;
+mod name_format;
+mod import;
+mod formats;
+mod birthdays;
+mod mail;
+mod qr;
 
 use crate::storage::*;
 use clap::Parser;
@@ -16,16 +31,40 @@ use crate::error::TuppError;
 
 fn main() -> Result<(), TuppError> {
     let cli = Cli::parse();
+    let output = output::Output {
+        format: cli.output,
+        columns: cli.columns,
+    };
+
+    config::init(config::load_config()?);
 
     let contacts_file = ensure_config_file()?;
     let mut data = load_data(&contacts_file)?;
     
     match cli.command {
         Commands::Contact { command } => {
-            commands::handle_contact_command(command, &mut data, &contacts_file)?;
+            commands::handle_contact_command(command, &mut data, &contacts_file, &output)?;
         },
         Commands::Group { command } => {
-            commands::handle_group_command(command, &mut data, &contacts_file)?;
+            commands::handle_group_command(command, &mut data, &contacts_file, &output)?;
+        },
+        Commands::Import { path, format, mapping, dry_run, on_duplicate, min_score } => {
+            commands::handle_import_command(&path, format, mapping.as_deref(), dry_run, on_duplicate, min_score, &mut data, &contacts_file)?;
+        },
+        Commands::Diff { a, b } => {
+            commands::handle_diff_command(&a, b.as_deref(), &data)?;
+        },
+        Commands::MergeFile { other, base, prefer, dry_run } => {
+            commands::handle_merge_file_command(&other, base.as_deref(), prefer, dry_run, &mut data, &contacts_file)?;
+        },
+        Commands::Birthdays { days } => {
+            commands::handle_birthdays_command(days, &data, &output)?;
+        },
+        Commands::Query { text, no_header } => {
+            commands::handle_query_command(&text, no_header, &data)?;
+        },
+        Commands::AddFromMail { from_only, dry_run } => {
+            commands::handle_add_from_mail_command(from_only, dry_run, &mut data, &contacts_file)?;
         },
         Commands::Serve { port } => {
             commands::handle_serve_command(port, &contacts_file)?;
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::fmt;
+
+/// How well a search query matched a value, from weakest to strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+    /// The query is close to the value, or to one of its words, despite typos.
+    Fuzzy,
+    Substring,
+    /// The value, or one of its words, starts with the query.
+    Prefix,
+    Exact,
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchKind::Exact => write!(f, "exact"),
+            MatchKind::Prefix => write!(f, "prefix"),
+            MatchKind::Substring => write!(f, "substring"),
+            MatchKind::Fuzzy => write!(f, "fuzzy"),
+        }
+    }
+}
+
+/// Match a query against a value, both already folded with `sanitize::fold`.
+/// Fuzzy matches need a similarity of at least `threshold` (0 to 1).
+pub fn match_kind(query: &str, value: &str, threshold: f64) -> Option<MatchKind> {
+    if query.is_empty() {
+        None
+    } else if value == query {
+        Some(MatchKind::Exact)
+    } else if value.starts_with(query) || value.split_whitespace().any(|w| w.starts_with(query)) {
+        Some(MatchKind::Prefix)
+    } else if value.contains(query) {
+        Some(MatchKind::Substring)
+    } else if query.chars().count() >= 3 && similarity(query, value) >= threshold {
+        Some(MatchKind::Fuzzy)
+    } else {
+        None
+    }
+}
+
+/// Similarity between a query and a value, or the closest of its words,
+/// as the best of the normalized edit distance and the trigram similarity.
+pub fn similarity(query: &str, value: &str) -> f64 {
+    std::iter::once(value)
+        .chain(value.split_whitespace())
+        .map(|candidate| {
+            let longest = query.chars().count().max(candidate.chars().count()) as f64;
+            let edit = 1.0 - levenshtein(query, candidate) as f64 / longest;
+            edit.max(trigram_similarity(query, candidate))
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Number of single-character insertions, deletions and substitutions
+/// needed to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Dice coefficient of the sets of character trigrams of both strings,
+/// padded so that word boundaries count.
+pub fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let trigrams = |s: &str| -> HashSet<[char; 3]> {
+        let padded: Vec<char> = format!("  {} ", s).chars().collect();
+        padded.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+    };
+
+    let (ta, tb) = (trigrams(a), trigrams(b));
+    if ta.is_empty() || tb.is_empty() {
+        return 0.0;
+    }
+    2.0 * ta.intersection(&tb).count() as f64 / (ta.len() + tb.len()) as f64
+}
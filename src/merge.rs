@@ -0,0 +1,339 @@
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+use crate::contact::Contact;
+use crate::diff::{self, Entry};
+use crate::models::TuppData;
+use crate::error::TuppError;
+
+/// Which of the two merged contacts wins a conflicting field.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    A,
+    B,
+}
+
+/// Merge contact `b` into contact `a`, which keeps its identifier.
+///
+/// Lists (emails, phones, socials, groups, links, sources) are unioned by value, and
+/// every `Link` in the data pointing to `b` is rewritten to point to `a`.
+/// When a single-valued field is set on both sides with different values,
+/// `resolve` is called with the field name and both values rendered as JSON.
+pub fn merge_contacts(
+    data: &mut TuppData,
+    a: Uuid,
+    b: Uuid,
+    resolve: &mut dyn FnMut(&str, &str, &str) -> Side,
+) -> Result<(), TuppError> {
+    if a == b {
+        return Err(TuppError::Validation("Cannot merge a contact with itself".to_string()));
+    }
+
+    // Find both contacts before removing one, so that data is left untouched on error
+    let position = |id: Uuid| data.contacts.iter().position(|c| c.identifier == id)
+        .ok_or_else(|| TuppError::Other(format!("Contact {} not found", id)));
+    let b_index = position(b)?;
+    let a_index = position(a)?;
+
+    let other = data.contacts.remove(b_index);
+    let a_index = if a_index > b_index { a_index - 1 } else { a_index };
+    merge_into(&mut data.contacts[a_index], other, resolve);
+
+    // Rewrite references to the merged contact, dropping the duplicates this creates
+    for contact in &mut data.contacts {
+        if let Some(ref mut links) = contact.links {
+            // Existing links to the kept contact take precedence over rewritten ones
+            if links.iter().any(|link| link.target == a) {
+                links.retain(|link| link.target != b);
+            }
+            for link in links.iter_mut() {
+                if link.target == b {
+                    link.target = a;
+                }
+            }
+            let owner = contact.identifier;
+            let mut seen = std::collections::HashSet::new();
+            links.retain(|link| link.target != owner && seen.insert(link.target));
+            if links.is_empty() {
+                contact.links = None;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn merge_into(keep: &mut Contact, other: Contact, resolve: &mut dyn FnMut(&str, &str, &str) -> Side) {
+    let a = &mut keep.identity;
+    let b = other.identity;
+
+    a.title = pick("title", a.title.take(), b.title, resolve);
+    a.first_name = pick("first_name", a.first_name.take(), b.first_name, resolve);
+    a.middle_name = pick("middle_name", a.middle_name.take(), b.middle_name, resolve);
+    a.last_name = pick("last_name", a.last_name.take(), b.last_name, resolve);
+    a.post_nominal = pick("post_nominal", a.post_nominal.take(), b.post_nominal, resolve);
+    a.gender = pick("gender", a.gender.take(), b.gender, resolve);
+    a.birth_date = pick("birth_date", a.birth_date.take(), b.birth_date, resolve);
+    a.birth_location = pick("birth_location", a.birth_location.take(), b.birth_location, resolve);
+    a.birth_first_name = pick("birth_first_name", a.birth_first_name.take(), b.birth_first_name, resolve);
+    a.birth_middle_name = pick("birth_middle_name", a.birth_middle_name.take(), b.birth_middle_name, resolve);
+    a.birth_last_name = pick("birth_last_name", a.birth_last_name.take(), b.birth_last_name, resolve);
+    a.death_date = pick("death_date", a.death_date.take(), b.death_date, resolve);
+    a.death_location = pick("death_location", a.death_location.take(), b.death_location, resolve);
+    a.anniversary = pick("anniversary", a.anniversary.take(), b.anniversary, resolve);
+    if a.is_alive != b.is_alive && resolve("is_alive", &a.is_alive.to_string(), &b.is_alive.to_string()) == Side::B {
+        a.is_alive = b.is_alive;
+    }
+    keep.address = pick("address", keep.address.take(), other.address, resolve);
+
+    for email in other.emails.into_iter().flatten() {
+        let emails = keep.emails.get_or_insert_with(Vec::new);
+        let address = email.address.as_deref().map(str::to_lowercase);
+        if !emails.iter().any(|e| e.address.as_deref().map(str::to_lowercase) == address) {
+            let label = unique_label(email.label, emails.iter().map(|e| e.label.as_deref()));
+            emails.push(crate::models::Email { label, ..email });
+        }
+    }
+
+    for phone in other.phones.into_iter().flatten() {
+        let phones = keep.phones.get_or_insert_with(Vec::new);
+        if !phones.iter().any(|p| p.country_code == phone.country_code && p.number == phone.number) {
+            let label = unique_label(phone.label, phones.iter().map(|p| p.label.as_deref()));
+            phones.push(crate::models::PhoneNumber { label, ..phone });
+        }
+    }
+
+    for social in other.socials.into_iter().flatten() {
+        let socials = keep.socials.get_or_insert_with(Vec::new);
+        if !socials.iter().any(|s| s.network.eq_ignore_ascii_case(&social.network) && s.username == social.username) {
+            socials.push(social);
+        }
+    }
+
+    if let Some(groups) = other.groups {
+        keep.groups.get_or_insert_with(Default::default).extend(groups);
+    }
+
+    for source in other.sources.into_iter().flatten() {
+        let sources = keep.sources.get_or_insert_with(Vec::new);
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+
+    // Links are deduplicated by target once all references are rewritten
+    if let Some(links) = other.links {
+        keep.links.get_or_insert_with(Vec::new).extend(links);
+    }
+}
+
+fn pick<T: Serialize>(
+    field: &str,
+    a: Option<T>,
+    b: Option<T>,
+    resolve: &mut dyn FnMut(&str, &str, &str) -> Side,
+) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let a_json = serde_json::to_string(&a).unwrap_or_default();
+            let b_json = serde_json::to_string(&b).unwrap_or_default();
+            if a_json == b_json || resolve(field, &a_json, &b_json) == Side::A {
+                Some(a)
+            } else {
+                Some(b)
+            }
+        },
+        (a, b) => a.or(b),
+    }
+}
+
+/// Keep the label of an item coming from the merged contact, unless it is
+/// already taken, in which case a numeric suffix is added (`work-2`).
+pub fn unique_label<'a>(label: Option<String>, existing: impl Iterator<Item = Option<&'a str>> + Clone) -> Option<String> {
+    let label = label?;
+    if !existing.clone().any(|l| l == Some(label.as_str())) {
+        return Some(label);
+    }
+    (2..)
+        .map(|n| format!("{}-{}", label, n))
+        .find(|candidate| !existing.clone().any(|l| l == Some(candidate.as_str())))
+}
+
+/// A field both copies changed differently in a three-way merge, and the side that was kept.
+pub struct Conflict {
+    pub field: String,
+    pub kept: Side,
+}
+
+/// Three-way merge of two copies of a data file, `a` and `b`, that were
+/// edited separately since `base`, entry by entry (see `diff::entries`).
+///
+/// What changed on one side only is taken from that side: fields, added and
+/// deleted entries. Lists are merged by value, keeping the items added on
+/// either side and dropping those deleted on either side. When both sides
+/// changed a field differently, or one side deleted an entry the other
+/// changed, `resolve` is called like in `merge_contacts`. The `updated` time
+/// of a contact is the latest of both.
+///
+/// Without a base, nothing counts as deleted, and every difference is a conflict.
+pub fn merge_data(
+    base: Option<&TuppData>,
+    a: &TuppData,
+    b: &TuppData,
+    resolve: &mut dyn FnMut(&str, &str, &str) -> Side,
+) -> Result<(TuppData, Vec<Conflict>), TuppError> {
+    let base = base.map(diff::entries).transpose()?.unwrap_or_default();
+    let a = diff::entries(a)?;
+    let b = diff::entries(b)?;
+    let find = |entries: &[Entry], entry: &Entry| {
+        entries.iter().find(|e| e.kind == entry.kind && e.identifier == entry.identifier).cloned()
+    };
+
+    let mut conflicts = Vec::new();
+    let mut merged = Vec::new();
+
+    let in_a = a.iter().map(|e| (e, true));
+    let only_in_b = b.iter().filter(|e| find(&a, e).is_none()).map(|e| (e, false));
+    for (entry, from_a) in in_a.chain(only_in_b) {
+        let base_value = find(&base, entry).map(|e| e.value);
+        let a_value = if from_a { Some(entry.value.clone()) } else { None };
+        let b_value = if from_a { find(&b, entry).map(|e| e.value) } else { Some(entry.value.clone()) };
+
+        let value = match (&a_value, &b_value) {
+            (Some(a_value), Some(b_value)) => {
+                let mut value = merge_value(&entry.to_string(), "", base_value.as_ref(), a_value, b_value, resolve, &mut conflicts);
+                let latest = [a_value.get("updated"), b_value.get("updated")].into_iter().flatten().max_by_key(|u| u.as_str());
+                if let (Some(object), Some(latest)) = (value.as_object_mut(), latest) {
+                    object.insert("updated".to_string(), latest.clone());
+                }
+                Some(value)
+            },
+            // Deleted on one side: kept only if the other side changed it since
+            (kept, deleted) | (deleted, kept) if deleted.is_none() => match (&base_value, kept) {
+                (None, kept) => kept.clone(),
+                (Some(base_value), Some(kept_value)) if base_value == kept_value => None,
+                _ => match conflict(entry.to_string(), a_value.as_ref(), b_value.as_ref(), resolve, &mut conflicts) {
+                    Side::A => a_value.clone(),
+                    Side::B => b_value.clone(),
+                },
+            },
+            _ => None,
+        };
+        if let Some(value) = value {
+            merged.push(Entry { value, ..entry.clone() });
+        }
+    }
+
+    Ok((diff::from_entries(merged)?, conflicts))
+}
+
+/// Merge the values of the field at `path` (dotted) of an entry.
+fn merge_value(
+    entry: &str,
+    path: &str,
+    base: Option<&Value>,
+    a: &Value,
+    b: &Value,
+    resolve: &mut dyn FnMut(&str, &str, &str) -> Side,
+    conflicts: &mut Vec<Conflict>,
+) -> Value {
+    let base = base.filter(|v| !v.is_null());
+    if a == b || base == Some(b) {
+        return a.clone();
+    }
+    if base == Some(a) {
+        return b.clone();
+    }
+
+    match (a, b) {
+        (Value::Object(a_map), Value::Object(b_map)) => {
+            let base_map = base.and_then(Value::as_object);
+            let mut merged = a_map.clone();
+            for key in a_map.keys().chain(b_map.keys().filter(|k| !a_map.contains_key(*k))) {
+                if key == "updated" {
+                    continue;
+                }
+                let null = Value::Null;
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                let value = merge_value(
+                    entry,
+                    &path,
+                    base_map.and_then(|m| m.get(key)),
+                    a_map.get(key).unwrap_or(&null),
+                    b_map.get(key).unwrap_or(&null),
+                    resolve,
+                    conflicts,
+                );
+                merged.insert(key.clone(), value);
+            }
+            Value::Object(merged)
+        },
+        (Value::Array(a_items), Value::Array(b_items)) => {
+            let base_items = base.and_then(Value::as_array).cloned().unwrap_or_default();
+            let deleted = |item: &Value| base_items.contains(item) && !(a_items.contains(item) && b_items.contains(item));
+            let mut merged: Vec<Value> = Vec::new();
+            for item in a_items.iter().chain(b_items) {
+                if !deleted(item) && !merged.contains(item) {
+                    merged.push(item.clone());
+                }
+            }
+            Value::Array(merged)
+        },
+        // Set on one side only
+        _ if base.is_none() && a.is_null() => b.clone(),
+        _ if base.is_none() && b.is_null() => a.clone(),
+        _ => match conflict(format!("{} of {}", path, entry), Some(a), Some(b), resolve, conflicts) {
+            Side::A => a.clone(),
+            Side::B => b.clone(),
+        },
+    }
+}
+
+/// Resolve a conflict, rendering a deleted entry as such, and record it.
+fn conflict(
+    field: String,
+    a: Option<&Value>,
+    b: Option<&Value>,
+    resolve: &mut dyn FnMut(&str, &str, &str) -> Side,
+    conflicts: &mut Vec<Conflict>,
+) -> Side {
+    let render = |v: Option<&Value>| v.map_or("(deleted)".to_string(), Value::to_string);
+    let kept = resolve(&field, &render(a), &render(b));
+    conflicts.push(Conflict { field, kept });
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Identity;
+
+    fn named(first: &str) -> Contact {
+        Contact::new(Identity { first_name: Some(first.to_string()), ..Identity::default() })
+    }
+
+    #[test]
+    fn a_missing_contact_leaves_data_untouched() {
+        let mut data = TuppData::new();
+        data.contacts = vec![named("Ada")];
+        let b = data.contacts[0].identifier;
+
+        let result = merge_contacts(&mut data, Uuid::new_v4(), b, &mut |_, _, _| Side::A);
+        assert!(result.is_err());
+        assert_eq!(data.contacts.len(), 1);
+    }
+
+    #[test]
+    fn the_kept_contact_may_come_after_the_merged_one() {
+        let mut data = TuppData::new();
+        let mut ada = named("Ada");
+        ada.identity.last_name = Some("Lovelace".to_string());
+        data.contacts = vec![ada, named("Alan"), named("Ada")];
+        let (a, b) = (data.contacts[2].identifier, data.contacts[0].identifier);
+
+        merge_contacts(&mut data, a, b, &mut |_, _, _| Side::A).unwrap();
+        assert_eq!(data.contacts.len(), 2);
+        assert_eq!(data.contacts[1].identifier, a);
+        assert_eq!(data.contacts[1].identity.last_name.as_deref(), Some("Lovelace"));
+    }
+}
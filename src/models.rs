@@ -1,6 +1,7 @@
 // use crate::unwrap::UnwrapString;
 use serde::{ Deserialize, Serialize };
 use std::fmt;
+use std::str::FromStr;
 use crate::contact::Contact;
 use crate::group::Group;
 
@@ -35,12 +36,21 @@ pub enum Gender {
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[allow(dead_code)]
 pub struct Company {
     pub name: Option<String>,
     pub position: Option<String>,
     pub address: Option<String>,
 }
 
+/// The identifier of a contact in the place it was imported from, so that
+/// importing from there again updates the contact.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Source {
+    pub importer: String,
+    pub uid: String,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Email {
     pub label: Option<String>,
@@ -55,7 +65,7 @@ impl fmt::Display for Email {
             self.label.as_deref().unwrap_or_default(),
             self.address.as_deref().unwrap_or_default()
         )?;
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -63,7 +73,55 @@ impl fmt::Display for Email {
 pub struct PhoneNumber {
     pub label: Option<String>,
     pub country_code: u16,
-    pub number: u32,
+    pub number: u64,
+}
+
+/// Calling codes of one or two digits. Calling codes are prefix-free, so any
+/// other code is three digits long.
+const SHORT_COUNTRY_CODES: &[&str] = &[
+    "1", "7", "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45",
+    "46", "47", "48", "49", "51", "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63",
+    "64", "65", "66", "81", "82", "84", "86", "90", "91", "92", "93", "94", "95", "98",
+];
+
+impl PhoneNumber {
+    /// Parse a number written the way people and other apps do
+    /// (`+33 6 12 34 56 78`, `0033612345678`, `06.12.34.56.78`). National
+    /// numbers lose their leading `0` and get `default_country_code`.
+    pub fn parse(label: Option<String>, text: &str, default_country_code: u16) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix("tel:").unwrap_or(text).replace("(0)", "");
+        // Drop extensions and dialing instructions
+        let text = text.split([';', ',', 'x', 'X', 'p', 'w']).next()?.trim();
+
+        let (international, rest) = match (text.strip_prefix('+'), text.strip_prefix("00")) {
+            (Some(rest), _) | (None, Some(rest)) => (true, rest),
+            _ => (false, text),
+        };
+        if rest.chars().any(|c| !c.is_ascii_digit() && !" -./()".contains(c)) {
+            return None;
+        }
+        let digits: String = rest.chars().filter(char::is_ascii_digit).collect();
+
+        let (country_code, national) = if international {
+            let length = SHORT_COUNTRY_CODES
+                .iter()
+                .find(|code| digits.starts_with(*code))
+                .map_or(3, |code| code.len());
+            (digits.get(..length)?.parse().ok()?, &digits[length..])
+        } else {
+            (default_country_code, digits.strip_prefix('0').unwrap_or(&digits))
+        };
+        if national.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            label,
+            country_code,
+            number: national.parse().ok()?,
+        })
+    }
 }
 
 impl fmt::Display for PhoneNumber {
@@ -75,7 +133,7 @@ impl fmt::Display for PhoneNumber {
             self.country_code,
             self.number
         )?;
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -95,7 +153,7 @@ impl fmt::Display for Social {
             self.network,
             self.username.as_deref().unwrap_or("N/A")
         )?;
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -124,7 +182,7 @@ impl fmt::Display for Address {
             self.country.clone().unwrap_or_default()
         )?;
 
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -164,7 +222,45 @@ impl fmt::Display for Date {
             self.day.unwrap_or_default()
         )?;
 
-        return Ok(());
+        Ok(())
+    }
+}
+
+/// Parse the `YEAR-MONTH-DAY` form produced by `Display`, where a `0` or empty
+/// component stands for an unknown part (e.g. `0-6-18` or `1944--`).
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split('-').collect();
+        if parts.len() != 3 {
+            return Err(format!("Invalid date '{}', expected YYYY-MM-DD", s));
+        }
+
+        fn part<T: FromStr + Default + PartialEq>(p: &str, s: &str) -> Result<Option<T>, String> {
+            if p.is_empty() {
+                return Ok(None);
+            }
+            let value: T = p.parse().map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", s))?;
+            Ok(if value == T::default() { None } else { Some(value) })
+        }
+
+        let year = part::<i32>(parts[0], s)?;
+        let month = part::<u8>(parts[1], s)?;
+        let day = part::<u8>(parts[2], s)?;
+
+        if month.is_some_and(|m| m > 12) || day.is_some_and(|d| d > 31) {
+            return Err(format!("Invalid date '{}', month or day out of range", s));
+        }
+
+        Ok(Self {
+            year,
+            month,
+            day,
+            hour: None,
+            minute: None,
+            second: None,
+        })
     }
 }
 
@@ -200,8 +296,32 @@ pub struct Identity {
     pub is_alive: bool,
     pub death_date: Option<Date>,
     pub death_location: Option<Address>,
+    /// Wedding or other anniversary date.
+    pub anniversary: Option<Date>,
 }
 
 fn default_is_alive() -> bool {
     true
 }
+
+impl Default for Identity {
+    fn default() -> Self {
+        Self {
+            title: None,
+            last_name: None,
+            middle_name: None,
+            first_name: None,
+            post_nominal: None,
+            gender: None,
+            birth_date: None,
+            birth_location: None,
+            birth_first_name: None,
+            birth_middle_name: None,
+            birth_last_name: None,
+            is_alive: true,
+            death_date: None,
+            death_location: None,
+            anniversary: None,
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::sync::LazyLock;
+use crate::error::TuppError;
+use crate::models::Identity;
+
+/// Built-in presets, which can be overridden in the `name_formats` setting.
+const PRESETS: &[(&str, &str)] = &[
+    ("formal", "[{title} ]{first}[ {middle}] {last}[, {post}]"),
+    ("sort", "{last:upper}[, {first}][ {middle:initial}.]"),
+    ("short", "{first}[ {last:initial}.]"),
+];
+
+/// Words replaced by fields in the legacy `TITLE FIRST LAST` patterns.
+const LEGACY_WORDS: &[(&str, &str)] = &[
+    ("TITLE", "title"),
+    ("FIRST", "first"),
+    ("MIDDLE", "middle"),
+    ("LAST", "last"),
+    ("POST", "post"),
+];
+
+/// The names tupp shows and matches by itself, parsed once. Unlike presets,
+/// they cannot be overridden in the configuration.
+pub static FIRST_LAST: LazyLock<NameFormat> = LazyLock::new(|| NameFormat::builtin("FIRST LAST"));
+pub static LAST_FIRST: LazyLock<NameFormat> = LazyLock::new(|| NameFormat::builtin("LAST FIRST"));
+pub static FIRST_MIDDLE_LAST: LazyLock<NameFormat> = LazyLock::new(|| NameFormat::builtin("FIRST MIDDLE LAST"));
+pub static FULL_NAME: LazyLock<NameFormat> = LazyLock::new(|| NameFormat::builtin("TITLE FIRST MIDDLE LAST POST"));
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Title,
+    First,
+    Middle,
+    Last,
+    Post,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Modifier {
+    Initial,
+    Upper,
+    Lower,
+}
+
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Field(Field, Option<Modifier>),
+    /// Rendered only when every field it contains is set.
+    Optional(Vec<Part>),
+}
+
+/// A parsed name template, such as `{first}[ {middle:initial}.] {last:upper}`.
+///
+/// Fields are `title`, `first`, `middle`, `last` and `post`, optionally
+/// followed by a modifier: `initial`, `upper` or `lower`. Text in brackets is
+/// dropped when one of its fields is empty, and `\` escapes the next character.
+#[derive(Debug)]
+pub struct NameFormat {
+    parts: Vec<Part>,
+}
+
+impl NameFormat {
+    /// Resolve a format given by the user: a preset name, a template, or a
+    /// legacy pattern made of `TITLE`, `FIRST`, `MIDDLE`, `LAST` and `POST`.
+    pub fn resolve(format: &str) -> Result<Self, TuppError> {
+        let configured = crate::config::config().name_formats.get(format);
+        let template = match configured {
+            Some(template) => template.as_str(),
+            None => PRESETS
+                .iter()
+                .find(|(name, _)| *name == format)
+                .map(|(_, template)| *template)
+                .unwrap_or(format),
+        };
+
+        if template.contains(['{', '[']) {
+            Self::parse(template)
+        } else {
+            Self::parse(&from_legacy(template))
+        }
+    }
+
+    /// A legacy pattern of tupp itself, which is known to be valid.
+    fn builtin(pattern: &str) -> Self {
+        Self::parse(&from_legacy(pattern)).expect("built-in name formats are valid")
+    }
+
+    pub fn parse(template: &str) -> Result<Self, TuppError> {
+        let mut chars = template.chars().peekable();
+        let parts = parse_parts(&mut chars, false)?;
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, identity: &Identity) -> String {
+        let mut out = String::new();
+        render_parts(&self.parts, identity, &mut out);
+        crate::sanitize::trim_extra_spaces(&out)
+    }
+}
+
+fn parse_parts(chars: &mut std::iter::Peekable<std::str::Chars>, nested: bool) -> Result<Vec<Part>, TuppError> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) => text.push(escaped),
+                None => return Err(invalid("trailing '\\'")),
+            },
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err(invalid("missing '}'")),
+                    }
+                }
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(parse_field(&spec)?);
+            },
+            '[' => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Optional(parse_parts(chars, true)?));
+            },
+            ']' if nested => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(text));
+                }
+                return Ok(parts);
+            },
+            '}' | ']' => return Err(invalid(&format!("unexpected '{}'", c))),
+            c => text.push(c),
+        }
+    }
+
+    if nested {
+        return Err(invalid("missing ']'"));
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+fn parse_field(spec: &str) -> Result<Part, TuppError> {
+    let (name, modifier) = match spec.split_once(':') {
+        Some((name, modifier)) => (name.trim(), Some(modifier.trim())),
+        None => (spec.trim(), None),
+    };
+
+    let field = match name {
+        "title" => Field::Title,
+        "first" => Field::First,
+        "middle" => Field::Middle,
+        "last" => Field::Last,
+        "post" => Field::Post,
+        other => return Err(invalid(&format!("unknown field '{}'", other))),
+    };
+    let modifier = match modifier {
+        None => None,
+        Some("initial") => Some(Modifier::Initial),
+        Some("upper") => Some(Modifier::Upper),
+        Some("lower") => Some(Modifier::Lower),
+        Some(other) => return Err(invalid(&format!("unknown modifier '{}'", other))),
+    };
+    Ok(Part::Field(field, modifier))
+}
+
+/// Render parts into `out`, returning false if a field was empty.
+fn render_parts(parts: &[Part], identity: &Identity, out: &mut String) -> bool {
+    let mut complete = true;
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Field(field, modifier) => {
+                let value = field_value(identity, *field).trim();
+                if value.is_empty() {
+                    complete = false;
+                    continue;
+                }
+                match modifier {
+                    None => out.push_str(value),
+                    Some(Modifier::Initial) => out.extend(value.chars().next().into_iter().flat_map(char::to_uppercase)),
+                    Some(Modifier::Upper) => out.push_str(&value.to_uppercase()),
+                    Some(Modifier::Lower) => out.push_str(&value.to_lowercase()),
+                }
+            },
+            Part::Optional(inner) => {
+                let mut segment = String::new();
+                if render_parts(inner, identity, &mut segment) {
+                    out.push_str(&segment);
+                }
+            },
+        }
+    }
+    complete
+}
+
+fn field_value(identity: &Identity, field: Field) -> &str {
+    let value = match field {
+        Field::Title => &identity.title,
+        Field::First => &identity.first_name,
+        Field::Middle => &identity.middle_name,
+        Field::Last => &identity.last_name,
+        Field::Post => &identity.post_nominal,
+    };
+    value.as_deref().unwrap_or_default()
+}
+
+/// Turn a legacy pattern into a template, replacing whole words only, so
+/// that `FIRSTNAME` or a name containing `LAST` is left untouched.
+fn from_legacy(pattern: &str) -> String {
+    let mut out = String::new();
+    let mut word = String::new();
+
+    let flush = |word: &mut String, out: &mut String| {
+        match LEGACY_WORDS.iter().find(|(legacy, _)| *legacy == word.as_str()) {
+            Some((_, field)) => out.push_str(&format!("{{{}}}", field)),
+            None => out.push_str(word),
+        }
+        word.clear();
+    };
+
+    for c in pattern.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            if matches!(c, '\\' | '}' | ']') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+fn invalid(reason: &str) -> TuppError {
+    TuppError::Validation(format!("Invalid name format: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(title: &str, first: &str, middle: &str, last: &str, post: &str) -> Identity {
+        let field = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        Identity {
+            title: field(title),
+            first_name: field(first),
+            middle_name: field(middle),
+            last_name: field(last),
+            post_nominal: field(post),
+            ..Identity::default()
+        }
+    }
+
+    fn render(format: &str, identity: &Identity) -> String {
+        NameFormat::resolve(format).unwrap().render(identity)
+    }
+
+    #[test]
+    fn presets_render_full_names() {
+        let grace = identity("Rear Admiral", "Grace", "Brewster", "Hopper", "PhD");
+        assert_eq!(render("formal", &grace), "Rear Admiral Grace Brewster Hopper, PhD");
+        assert_eq!(render("sort", &grace), "HOPPER, Grace B.");
+        assert_eq!(render("short", &grace), "Grace H.");
+    }
+
+    #[test]
+    fn optional_segments_collapse_when_empty() {
+        let ada = identity("", "Ada", "", "Lovelace", "");
+        assert_eq!(render("formal", &ada), "Ada Lovelace");
+        assert_eq!(render("sort", &ada), "LOVELACE, Ada");
+        assert_eq!(render("[({first} {last})]", &identity("", "Ada", "", "", "")), "");
+        assert_eq!(render("{first}[ {middle:initial}.] {last:lower}", &ada), "Ada lovelace");
+        assert_eq!(render("short", &identity("", "", "", "Turing", "")), "T.");
+    }
+
+    #[test]
+    fn legacy_patterns_replace_whole_words_only() {
+        let ada = identity("Countess", "Ada", "", "Lovelace", "");
+        assert_eq!(from_legacy("LAST, FIRST"), "{last}, {first}");
+        assert_eq!(render("LAST, FIRST", &ada), "Lovelace, Ada");
+        assert_eq!(render("TITLE FIRST MIDDLE LAST", &ada), "Countess Ada Lovelace");
+        assert_eq!(render("FIRSTNAME LAST", &ada), "FIRSTNAME Lovelace");
+        assert_eq!(from_legacy("FIRST] LAST\\"), "{first}\\] {last}\\\\");
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        assert!(NameFormat::parse("{first").is_err());
+        assert!(NameFormat::parse("[{first}").is_err());
+        assert!(NameFormat::parse("{nickname}").is_err());
+        assert!(NameFormat::parse("{first:title}").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use serde_json::{Map, Value};
+use crate::error::TuppError;
+
+/// A machine-readable output format for read commands.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Jsonl,
+    Csv,
+    Tsv,
+    Table,
+    Yaml,
+}
+
+/// How read commands print their results: human text when no format is
+/// chosen, otherwise records in the chosen format, optionally restricted
+/// to some columns.
+pub struct Output {
+    pub format: Option<OutputFormat>,
+    pub columns: Vec<String>,
+}
+
+/// Short column names accepted in `--columns`, for the most used fields.
+const ALIASES: &[(&str, &str)] = &[
+    ("id", "identifier"),
+    ("title", "identity.title"),
+    ("first", "identity.first_name"),
+    ("middle", "identity.middle_name"),
+    ("last", "identity.last_name"),
+    ("post", "identity.post_nominal"),
+    ("gender", "identity.gender"),
+    ("alive", "identity.is_alive"),
+    ("birth", "identity.birth_date"),
+    ("death", "identity.death_date"),
+    ("anniversary", "identity.anniversary"),
+];
+
+impl Output {
+    pub fn is_human(&self) -> bool {
+        self.format.is_none()
+    }
+
+    /// Print a list of records, given as their serde representation.
+    /// Tabular formats use `default_columns` unless columns were chosen.
+    pub fn print_records(&self, records: &[Value], default_columns: &[&str]) -> Result<(), TuppError> {
+        let format = match self.format {
+            Some(format) => format,
+            None => return Ok(()),
+        };
+
+        match format {
+            OutputFormat::Json => {
+                let records: Vec<Value> = records.iter().map(|r| self.project(r)).collect();
+                println!("{}", serde_json::to_string_pretty(&records)?);
+            },
+            OutputFormat::Jsonl => {
+                for record in records {
+                    println!("{}", serde_json::to_string(&self.project(record))?);
+                }
+            },
+            OutputFormat::Yaml => {
+                let records: Vec<Value> = records.iter().map(|r| self.project(r)).collect();
+                print!("{}", to_yaml(&records)?);
+            },
+            OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Table => {
+                let columns = self.columns_or(default_columns);
+                let rows: Vec<Vec<String>> = records
+                    .iter()
+                    .map(|r| columns.iter().map(|c| cell_text(&lookup(r, c))).collect())
+                    .collect();
+                print_table(format, &columns, &rows);
+            },
+        }
+        Ok(())
+    }
+
+    /// Print a single record, as an object rather than a one-item list
+    /// for JSON and YAML.
+    pub fn print_record(&self, record: &Value, default_columns: &[&str]) -> Result<(), TuppError> {
+        match self.format {
+            Some(OutputFormat::Json) => println!("{}", serde_json::to_string_pretty(&self.project(record))?),
+            Some(OutputFormat::Yaml) => print!("{}", to_yaml(&self.project(record))?),
+            _ => self.print_records(std::slice::from_ref(record), default_columns)?,
+        }
+        Ok(())
+    }
+
+    fn columns_or(&self, default_columns: &[&str]) -> Vec<String> {
+        if self.columns.is_empty() {
+            default_columns.iter().map(|c| c.to_string()).collect()
+        } else {
+            self.columns.clone()
+        }
+    }
+
+    /// Keep only the chosen columns of a record, if any were chosen. They
+    /// are keyed by their path, so that aliases never reach scripts.
+    fn project(&self, record: &Value) -> Value {
+        if self.columns.is_empty() {
+            return record.clone();
+        }
+        let mut map = Map::new();
+        for column in &self.columns {
+            map.insert(column_path(column).to_string(), lookup(record, column));
+        }
+        Value::Object(map)
+    }
+}
+
+/// The dotted path of a column, which may be given by its alias.
+fn column_path(column: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == column)
+        .map(|(_, path)| *path)
+        .unwrap_or(column)
+}
+
+/// Resolve a dotted column path in a record. In lists, a segment is either an
+/// index or the label (or network, for socials) of an item: `emails.work`.
+fn lookup(record: &Value, column: &str) -> Value {
+    let mut current = record;
+    for segment in column_path(column).split('.') {
+        let next = match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => match segment.parse::<usize>() {
+                Ok(index) => items.get(index),
+                Err(_) => items.iter().find(|item| {
+                    item.get("label").and_then(Value::as_str) == Some(segment)
+                        || item.get("network").and_then(Value::as_str).is_some_and(|n| n.eq_ignore_ascii_case(segment))
+                }),
+            },
+            _ => None,
+        };
+        match next {
+            Some(value) => current = value,
+            None => return Value::Null,
+        }
+    }
+    current.clone()
+}
+
+/// Render a value for a table cell: objects of known shapes are shown the
+/// way a person would write them, and lists are joined with "; ".
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(cell_text).filter(|s| !s.is_empty()).collect::<Vec<_>>().join("; "),
+        Value::Object(map) => {
+            let text = |key: &str| map.get(key).map(cell_text).unwrap_or_default();
+            if map.contains_key("country_code") {
+                format!("+{} {}", text("country_code"), text("number"))
+            } else if map.contains_key("address") {
+                text("address")
+            } else if map.contains_key("network") {
+                format!("{}:{}", text("network"), text("username"))
+            } else if map.contains_key("year") {
+                let part = |key: &str, width: usize| match map.get(key).and_then(Value::as_i64) {
+                    Some(n) => format!("{:0width$}", n, width = width),
+                    None => "-".repeat(width),
+                };
+                format!("{}-{}-{}", part("year", 4), part("month", 2), part("day", 2))
+            } else if map.contains_key("street") || map.contains_key("city") {
+                ["number", "street", "post_code", "city", "region", "country"]
+                    .iter()
+                    .map(|key| text(key))
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            } else if map.contains_key("target") {
+                format!("{}:{}", text("relation"), text("target"))
+            } else {
+                value.to_string()
+            }
+        },
+        other => other.to_string(),
+    }
+}
+
+fn print_table(format: OutputFormat, columns: &[String], rows: &[Vec<String>]) {
+    match format {
+        OutputFormat::Csv => {
+            let line = |cells: &[String]| cells.iter().map(|c| csv_escape(c)).collect::<Vec<_>>().join(",");
+            println!("{}", line(columns));
+            for row in rows {
+                println!("{}", line(row));
+            }
+        },
+        OutputFormat::Tsv => {
+            let line = |cells: &[String]| cells.iter().map(|c| c.replace(['\t', '\n', '\r'], " ")).collect::<Vec<_>>().join("\t");
+            println!("{}", line(columns));
+            for row in rows {
+                println!("{}", line(row));
+            }
+        },
+        _ => {
+            let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+            for row in rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+            let line = |cells: &[String]| {
+                cells.iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.chars().count())))
+                    .collect::<Vec<_>>()
+                    .join("  ")
+                    .trim_end()
+                    .to_string()
+            };
+            println!("{}", line(columns));
+            println!("{}", widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("  "));
+            for row in rows {
+                println!("{}", line(row));
+            }
+        },
+    }
+}
+
+pub fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+fn to_yaml<T: serde::Serialize>(value: &T) -> Result<String, TuppError> {
+    serde_yaml::to_string(value).map_err(|e| TuppError::Other(format!("Cannot serialize to YAML: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn chosen_columns_are_keyed_by_their_path() {
+        let output = Output {
+            format: Some(OutputFormat::Json),
+            columns: vec!["first".to_string(), "identity.last_name".to_string(), "emails.work".to_string()],
+        };
+        let record = json!({
+            "identity": { "first_name": "Ada", "last_name": "Lovelace" },
+            "emails": [{ "label": "work", "address": "ada@example.org" }],
+        });
+        assert_eq!(output.project(&record), json!({
+            "identity.first_name": "Ada",
+            "identity.last_name": "Lovelace",
+            "emails.work": { "label": "work", "address": "ada@example.org" },
+        }));
+    }
+}
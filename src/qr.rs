@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use qrcode::{Color, EcLevel, QrCode};
+use crate::contact::Contact;
+use crate::formats::vcard;
+use crate::name_format::FIRST_LAST;
+use crate::error::TuppError;
+
+/// Light modules around the code, as the QR specification asks for.
+const QUIET_ZONE: usize = 4;
+
+/// Pixels per module in PNG images.
+const PNG_SCALE: usize = 8;
+
+/// How the contact is encoded in the code.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum QrKind {
+    /// vCard 3.0, read by most phone cameras.
+    Vcard,
+    /// MECARD, shorter, so the code is smaller and easier to scan.
+    Mecard,
+}
+
+/// The fields a code can hold. The name is always included.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum QrField {
+    Phones,
+    Emails,
+    Address,
+    Birthday,
+}
+
+/// The text of the code of a contact, with only `fields`, or all of them.
+pub fn payload(contact: &Contact, kind: QrKind, fields: &[QrField]) -> String {
+    let has = |field: QrField| fields.is_empty() || fields.contains(&field);
+    let identity = &contact.identity;
+    let name = contact.format_name(&FIRST_LAST);
+    let phones: Vec<String> = contact.phones.iter().flatten()
+        .map(|p| format!("+{}{}", p.country_code, p.number))
+        .collect();
+    let emails: Vec<&String> = contact.emails.iter().flatten().filter_map(|e| e.address.as_ref()).collect();
+    let birthday = identity.birth_date.as_ref()
+        .filter(|d| d.year.is_some() && d.month.is_some() && d.day.is_some())
+        .map(|d| format!("{:04}{:02}{:02}", d.year.unwrap_or_default(), d.month.unwrap_or_default(), d.day.unwrap_or_default()));
+    let street = contact.address.as_ref().map(|a| {
+        [&a.number, &a.street].into_iter().flatten().cloned().collect::<Vec<_>>().join(" ")
+    });
+
+    match kind {
+        QrKind::Vcard => {
+            let text = |value: &Option<String>| value.as_deref().map(vcard::escape).unwrap_or_default();
+            let mut lines = vec![
+                "BEGIN:VCARD".to_string(),
+                "VERSION:3.0".to_string(),
+                format!("N:{};{};{};{};{}", text(&identity.last_name), text(&identity.first_name), text(&identity.middle_name), text(&identity.title), text(&identity.post_nominal)),
+                format!("FN:{}", vcard::escape(&name)),
+            ];
+            if has(QrField::Phones) {
+                for (phone, number) in contact.phones.iter().flatten().zip(&phones) {
+                    match phone.label.as_deref().filter(|l| l.chars().all(|c| c.is_ascii_alphanumeric()) && *l != "default") {
+                        Some(label) => lines.push(format!("TEL;TYPE={}:{}", label.to_uppercase(), number)),
+                        None => lines.push(format!("TEL:{}", number)),
+                    }
+                }
+            }
+            if has(QrField::Emails) {
+                lines.extend(emails.iter().map(|e| format!("EMAIL:{}", vcard::escape(e))));
+            }
+            if has(QrField::Address) {
+                if let Some(address) = &contact.address {
+                    lines.push(format!(
+                        "ADR:;;{};{};{};{};{}",
+                        text(&street), text(&address.city), text(&address.region), text(&address.post_code), text(&address.country),
+                    ));
+                }
+            }
+            if has(QrField::Birthday) {
+                lines.extend(birthday.map(|b| format!("BDAY:{}", b)));
+            }
+            lines.push("END:VCARD".to_string());
+            lines.join("\r\n")
+        },
+        QrKind::Mecard => {
+            let escape = |value: &str| {
+                value.chars().fold(String::new(), |mut out, c| {
+                    if "\\;,:\"".contains(c) {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                    out
+                })
+            };
+            let last_first = [&identity.last_name, &identity.first_name]
+                .into_iter()
+                .flatten()
+                .map(|n| escape(n))
+                .collect::<Vec<_>>()
+                .join(",");
+            let mut out = format!("MECARD:N:{};", if last_first.is_empty() { escape(&name) } else { last_first });
+            if has(QrField::Phones) {
+                phones.iter().for_each(|p| out.push_str(&format!("TEL:{};", p)));
+            }
+            if has(QrField::Emails) {
+                emails.iter().for_each(|e| out.push_str(&format!("EMAIL:{};", escape(e))));
+            }
+            if has(QrField::Address) {
+                if let Some(address) = &contact.address {
+                    let parts = [street.clone(), address.post_code.clone(), address.city.clone(), address.region.clone(), address.country.clone()];
+                    let text = parts.into_iter().flatten().filter(|p| !p.is_empty()).collect::<Vec<_>>().join(", ");
+                    out.push_str(&format!("ADR:{};", escape(&text)));
+                }
+            }
+            if has(QrField::Birthday) {
+                if let Some(birthday) = birthday {
+                    out.push_str(&format!("BDAY:{};", birthday));
+                }
+            }
+            out.push(';');
+            out
+        },
+    }
+}
+
+/// Encode a payload, or explain that it does not fit in a code.
+pub fn encode(payload: &str) -> Result<QrCode, TuppError> {
+    QrCode::with_error_correction_level(payload, EcLevel::M).map_err(|e| {
+        TuppError::Validation(format!("The contact does not fit in a QR code ({}), try --only or --kind mecard", e))
+    })
+}
+
+/// Whether the module at `(x, y)`, counted from the edge of the quiet zone, is dark.
+fn is_dark(code: &QrCode, colors: &[Color], x: usize, y: usize) -> bool {
+    let width = code.width();
+    let (x, y) = (x.wrapping_sub(QUIET_ZONE), y.wrapping_sub(QUIET_ZONE));
+    x < width && y < width && colors[y * width + x] == Color::Dark
+}
+
+/// Draw a code with half blocks, two modules per character. Light modules are
+/// drawn, so that the code reads on dark terminals, unless `invert` is set.
+pub fn render_terminal(code: &QrCode, invert: bool) -> String {
+    let colors = code.to_colors();
+    let size = code.width() + 2 * QUIET_ZONE;
+    let mut out = String::new();
+    for y in (0..size).step_by(2) {
+        for x in 0..size {
+            let top = is_dark(code, &colors, x, y) == invert;
+            let bottom = y + 1 < size && is_dark(code, &colors, x, y + 1) == invert;
+            out.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn render_svg(code: &QrCode) -> String {
+    code.render::<qrcode::render::svg::Color>()
+        .min_dimensions(256, 256)
+        .quiet_zone(true)
+        .build()
+}
+
+/// Write a code as a black and white PNG image.
+pub fn write_png(code: &QrCode, path: &Path) -> Result<(), TuppError> {
+    let colors = code.to_colors();
+    let size = (code.width() + 2 * QUIET_ZONE) * PNG_SCALE;
+    let mut pixels = Vec::with_capacity(size * size);
+    for y in 0..size {
+        for x in 0..size {
+            let dark = is_dark(code, &colors, x / PNG_SCALE, y / PNG_SCALE);
+            pixels.push(if dark { 0 } else { 255 });
+        }
+    }
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| TuppError::Other(format!("Cannot write {}: {}", path.display(), e)))
+}
@@ -0,0 +1,617 @@
+//! A small query language to filter contacts, e.g.
+//! `group:family AND birth.month:6 AND NOT email:*@oldcorp.com`.
+//!
+//! A query is made of terms `field<op>value`, combined with `AND`, `OR`, `NOT`
+//! and parentheses. Terms next to each other are implicitly joined by `AND`,
+//! and a bare value searches names, emails, phones and social handles.
+//!
+//! With `:`, text fields match if they contain the value, or match it as a
+//! pattern when it has `*` or `?` wildcards. `=` requires the whole value.
+//! Dates and numbers also support `<`, `<=`, `>` and `>=`, and dates can be
+//! partial (`birth>=1990`, `birth<1990-06`). Case and accents are ignored.
+
+use uuid::Uuid;
+use std::collections::HashSet;
+use crate::contact::Contact;
+use crate::group::Group;
+use crate::models::{Date, Gender, TuppData};
+use crate::error::TuppError;
+use crate::sanitize::fold;
+use crate::name_format::FIRST_MIDDLE_LAST;
+
+#[derive(Debug)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+#[derive(Debug)]
+pub struct Term {
+    pub field: Field,
+    pub op: Op,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Any,
+    Id,
+    Name,
+    Title,
+    First,
+    Middle,
+    Last,
+    PostNominal,
+    Gender,
+    Alive,
+    Email,
+    Phone,
+    Social,
+    Group,
+    Address,
+    City,
+    Country,
+    Birth(DatePart),
+    Death(DatePart),
+    Anniversary(DatePart),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DatePart {
+    Full,
+    Year,
+    Month,
+    Day,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Match,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, TuppError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let query = parser.parse_or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(query),
+            Some(token) => Err(TuppError::Query(format!("Unexpected '{}'", token))),
+        }
+    }
+
+    pub fn matches(&self, contact: &Contact, data: &TuppData) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(contact, data) && b.matches(contact, data),
+            Query::Or(a, b) => a.matches(contact, data) || b.matches(contact, data),
+            Query::Not(q) => !q.matches(contact, data),
+            Query::Term(term) => term.matches(contact, data),
+        }
+    }
+}
+
+/* TOKENIZER */
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Word(word) => write!(f, "{}", word),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, TuppError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' {
+            chars.next();
+            tokens.push(Token::LParen);
+        } else if c == ')' {
+            chars.next();
+            tokens.push(Token::RParen);
+        } else {
+            // A word runs until whitespace or a parenthesis, quotes included
+            let mut word = String::new();
+            let mut quoted = false;
+            while let Some(&c) = chars.peek() {
+                if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                    break;
+                }
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                word.push(c);
+                chars.next();
+            }
+            if quoted {
+                return Err(TuppError::Query(format!("Unterminated quote in '{}'", word)));
+            }
+            tokens.push(match word.as_str() {
+                "AND" | "&&" => Token::And,
+                "OR" | "||" => Token::Or,
+                "NOT" | "!" => Token::Not,
+                _ => Token::Word(word),
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+/* PARSER */
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_or(&mut self) -> Result<Query, TuppError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            let right = self.parse_and()?;
+            left = Query::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, TuppError> {
+        let mut left = self.parse_not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.position += 1,
+                // Juxtaposed terms are implicitly joined by AND
+                Some(Token::Word(_)) | Some(Token::Not) | Some(Token::LParen) => {},
+                _ => return Ok(left),
+            }
+            let right = self.parse_not()?;
+            left = Query::And(Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Query, TuppError> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(Query::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, TuppError> {
+        let token = self.tokens.get(self.position)
+            .ok_or_else(|| TuppError::Query("Unexpected end of query".to_string()))?;
+        self.position += 1;
+
+        match token {
+            Token::LParen => {
+                let query = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(TuppError::Query("Missing closing parenthesis".to_string()));
+                }
+                self.position += 1;
+                Ok(query)
+            },
+            Token::Word(word) => {
+                // A leading '-' negates a term
+                match word.strip_prefix('-') {
+                    Some(rest) if !rest.is_empty() => Ok(Query::Not(Box::new(Query::Term(parse_term(rest)?)))),
+                    _ => Ok(Query::Term(parse_term(word)?)),
+                }
+            },
+            other => Err(TuppError::Query(format!("Unexpected '{}'", other))),
+        }
+    }
+}
+
+fn parse_term(word: &str) -> Result<Term, TuppError> {
+    let operators = [
+        ("<=", Op::LessOrEqual),
+        (">=", Op::GreaterOrEqual),
+        ("!=", Op::NotEqual),
+        (":", Op::Match),
+        ("=", Op::Equal),
+        ("<", Op::Less),
+        (">", Op::Greater),
+    ];
+
+    // The operator is the first one found before any quote
+    let head = word.split('"').next().unwrap_or_default();
+    let found = operators
+        .iter()
+        .filter_map(|(symbol, op)| head.find(symbol).map(|index| (index, *symbol, *op)))
+        .min_by_key(|(index, symbol, _)| (*index, std::cmp::Reverse(symbol.len())));
+
+    let (field, op, value) = match found {
+        Some((index, symbol, op)) => (
+            parse_field(&word[..index])?,
+            op,
+            &word[index + symbol.len()..],
+        ),
+        None => (Field::Any, Op::Match, word),
+    };
+
+    let value = value.trim_matches('"').to_string();
+    if value.is_empty() {
+        return Err(TuppError::Query(format!("Missing value in '{}'", word)));
+    }
+
+    let ordered = matches!(op, Op::Less | Op::LessOrEqual | Op::Greater | Op::GreaterOrEqual);
+    if ordered && !matches!(field, Field::Birth(_) | Field::Death(_) | Field::Anniversary(_)) {
+        return Err(TuppError::Query(format!("'{}' cannot be compared with '<' or '>'", word)));
+    }
+
+    Ok(Term { field, op, value })
+}
+
+fn parse_field(name: &str) -> Result<Field, TuppError> {
+    let date_part = |part: Option<&str>| match part {
+        None => Ok(DatePart::Full),
+        Some("year") => Ok(DatePart::Year),
+        Some("month") => Ok(DatePart::Month),
+        Some("day") => Ok(DatePart::Day),
+        Some(other) => Err(TuppError::Query(format!("Unknown date part '{}'", other))),
+    };
+
+    let mut parts = name.splitn(2, '.');
+    let field = match (parts.next().unwrap_or_default().to_lowercase().as_str(), parts.next()) {
+        ("id", None) => Field::Id,
+        ("name", None) => Field::Name,
+        ("title", None) => Field::Title,
+        ("first", None) | ("first_name", None) => Field::First,
+        ("middle", None) | ("middle_name", None) => Field::Middle,
+        ("last", None) | ("last_name", None) => Field::Last,
+        ("post", None) | ("post_nominal", None) => Field::PostNominal,
+        ("gender", None) => Field::Gender,
+        ("alive", None) | ("is_alive", None) => Field::Alive,
+        ("email", None) => Field::Email,
+        ("phone", None) => Field::Phone,
+        ("social", None) => Field::Social,
+        ("group", None) => Field::Group,
+        ("address", None) => Field::Address,
+        ("city", None) => Field::City,
+        ("country", None) => Field::Country,
+        ("birth", part) => Field::Birth(date_part(part)?),
+        ("death", part) => Field::Death(date_part(part)?),
+        ("anniversary", part) => Field::Anniversary(date_part(part)?),
+        _ => return Err(TuppError::Query(format!("Unknown field '{}'", name))),
+    };
+    Ok(field)
+}
+
+/* EVALUATION */
+
+impl Term {
+    fn matches(&self, contact: &Contact, data: &TuppData) -> bool {
+        let identity = &contact.identity;
+        let texts = |values: Vec<Option<&String>>| self.match_texts(values.into_iter().flatten());
+
+        match self.field {
+            Field::Any => texts(vec![
+                identity.first_name.as_ref(),
+                identity.middle_name.as_ref(),
+                identity.last_name.as_ref(),
+            ]) || self.match_texts(contact.emails.iter().flatten().filter_map(|e| e.address.as_ref()))
+                || self.match_texts(contact.socials.iter().flatten().filter_map(|s| s.username.as_ref()))
+                || self.match_phones(contact),
+            Field::Id => self.match_texts([&contact.identifier.to_string()]),
+            Field::Name => texts(vec![
+                identity.title.as_ref(),
+                identity.first_name.as_ref(),
+                identity.middle_name.as_ref(),
+                identity.last_name.as_ref(),
+                identity.post_nominal.as_ref(),
+                identity.birth_first_name.as_ref(),
+                identity.birth_middle_name.as_ref(),
+                identity.birth_last_name.as_ref(),
+            ]) || self.match_texts([&contact.format_name(&FIRST_MIDDLE_LAST)]),
+            Field::Title => texts(vec![identity.title.as_ref()]),
+            Field::First => texts(vec![identity.first_name.as_ref()]),
+            Field::Middle => texts(vec![identity.middle_name.as_ref()]),
+            Field::Last => texts(vec![identity.last_name.as_ref()]),
+            Field::PostNominal => texts(vec![identity.post_nominal.as_ref()]),
+            Field::Gender => {
+                let gender = identity.gender.as_ref().map(|g| match g {
+                    Gender::Male => "male".to_string(),
+                    Gender::Female => "female".to_string(),
+                    Gender::NonBinary => "non-binary".to_string(),
+                });
+                self.with_equality(self.match_texts_exact(gender.iter()))
+            },
+            Field::Alive => {
+                let expected = matches!(fold(&self.value).as_str(), "true" | "yes" | "1");
+                self.with_equality(identity.is_alive == expected)
+            },
+            Field::Email => self.match_texts(contact.emails.iter().flatten().filter_map(|e| e.address.as_ref())),
+            Field::Phone => self.match_phones(contact),
+            Field::Social => self.match_texts(contact.socials.iter().flatten().flat_map(|s| {
+                std::iter::once(&s.network).chain(s.username.as_ref())
+            })),
+            Field::Group => {
+                let ids = self.matching_group_ids(&data.groups);
+                let member = contact.groups.iter().flatten().any(|id| ids.contains(id));
+                self.with_equality(member)
+            },
+            Field::Address => contact.address.as_ref().is_some_and(|a| {
+                self.match_texts([&a.label, &a.number, &a.street, &a.post_code, &a.city, &a.region, &a.country].into_iter().flatten())
+            }),
+            Field::City => self.match_texts(contact.address.as_ref().and_then(|a| a.city.as_ref())),
+            Field::Country => self.match_texts(contact.address.as_ref().and_then(|a| a.country.as_ref())),
+            Field::Birth(part) => self.match_date(identity.birth_date.as_ref(), part),
+            Field::Death(part) => self.match_date(identity.death_date.as_ref(), part),
+            Field::Anniversary(part) => self.match_date(identity.anniversary.as_ref(), part),
+        }
+    }
+
+    /// Negate the result of an equality test for `!=`.
+    fn with_equality(&self, equal: bool) -> bool {
+        if self.op == Op::NotEqual { !equal } else { equal }
+    }
+
+    fn match_texts<'a>(&self, values: impl IntoIterator<Item = &'a String>) -> bool {
+        let pattern = fold(&self.value);
+        let found = values.into_iter().any(|value| self.match_text(&pattern, &fold(value)));
+        self.with_equality(found)
+    }
+
+    /// Match a folded value against the folded pattern: as a wildcard
+    /// pattern, by containing it for `:`, or whole otherwise.
+    fn match_text(&self, pattern: &str, value: &str) -> bool {
+        if pattern.contains(['*', '?']) {
+            glob_match(pattern, value)
+        } else if self.op == Op::Match {
+            value.contains(pattern)
+        } else {
+            value == pattern
+        }
+    }
+
+    fn match_texts_exact<'a>(&self, values: impl IntoIterator<Item = &'a String>) -> bool {
+        let pattern = fold(&self.value);
+        values.into_iter().any(|v| glob_match(&pattern, &fold(v)))
+    }
+
+    fn match_phones(&self, contact: &Contact) -> bool {
+        let digits: String = self.value.chars().filter(|c| c.is_ascii_digit() || matches!(c, '*' | '?')).collect();
+        if digits.is_empty() {
+            return self.with_equality(false);
+        }
+        let wildcard = digits.contains(['*', '?']);
+        let found = contact.phones.iter().flatten().any(|p| {
+            [format!("{}{}", p.country_code, p.number), p.number.to_string(), format!("0{}", p.number)]
+                .iter()
+                .any(|value| {
+                    if wildcard {
+                        glob_match(&digits, value)
+                    } else if self.op == Op::Match {
+                        value.contains(&digits)
+                    } else {
+                        *value == digits
+                    }
+                })
+        });
+        self.with_equality(found)
+    }
+
+    /// Identifiers of the groups whose name matches, and of all their subgroups.
+    fn matching_group_ids(&self, groups: &[Group]) -> HashSet<Uuid> {
+        fn collect_all(group: &Group, ids: &mut HashSet<Uuid>) {
+            ids.insert(group.identifier);
+            for subgroup in &group.subgroups {
+                collect_all(subgroup, ids);
+            }
+        }
+
+        fn visit(term: &Term, groups: &[Group], pattern: &str, id: Option<Uuid>, ids: &mut HashSet<Uuid>) {
+            for group in groups {
+                if id == Some(group.identifier) || term.match_text(pattern, &fold(&group.name)) {
+                    collect_all(group, ids);
+                } else {
+                    visit(term, &group.subgroups, pattern, id, ids);
+                }
+            }
+        }
+
+        let mut ids = HashSet::new();
+        visit(self, groups, &fold(&self.value), Uuid::parse_str(&self.value).ok(), &mut ids);
+        ids
+    }
+
+    fn match_date(&self, date: Option<&Date>, part: DatePart) -> bool {
+        let date = match date {
+            Some(date) => date,
+            None => return self.op == Op::NotEqual,
+        };
+
+        let known: Vec<i64> = match part {
+            DatePart::Full => {
+                let mut known = Vec::new();
+                for component in [date.year.map(i64::from), date.month.map(i64::from), date.day.map(i64::from)] {
+                    match component {
+                        Some(value) => known.push(value),
+                        None => break,
+                    }
+                }
+                known
+            },
+            DatePart::Year => date.year.map(i64::from).into_iter().collect(),
+            DatePart::Month => date.month.map(i64::from).into_iter().collect(),
+            DatePart::Day => date.day.map(i64::from).into_iter().collect(),
+        };
+
+        let wanted: Vec<i64> = match self.value.split('-').map(|p| p.parse::<i64>()).collect::<Result<Vec<_>, _>>() {
+            Ok(wanted) if !wanted.is_empty() => wanted,
+            _ => return false,
+        };
+
+        // Compare only as precisely as the query asks. A less precise date
+        // can still be compared on the parts it has, unless they are equal:
+        // 1989 is before 1990-06, but 1990 may or may not be.
+        let common = known.len().min(wanted.len());
+        let ordering = known[..common].cmp(&wanted[..common]);
+        if ordering.is_eq() && known.len() < wanted.len() {
+            return self.op == Op::NotEqual;
+        }
+
+        match self.op {
+            Op::Match | Op::Equal => ordering.is_eq(),
+            Op::NotEqual => ordering.is_ne(),
+            Op::Less => ordering.is_lt(),
+            Op::LessOrEqual => ordering.is_le(),
+            Op::Greater => ordering.is_gt(),
+            Op::GreaterOrEqual => ordering.is_ge(),
+        }
+    }
+}
+
+/// Match a whole string against a pattern where `*` stands for any sequence
+/// of characters and `?` for a single one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Email, Identity};
+
+    fn contact(first: &str, last: &str, email: &str, birth: Option<&str>) -> Contact {
+        let mut contact = Contact::new(Identity {
+            first_name: Some(first.to_string()),
+            last_name: Some(last.to_string()),
+            birth_date: birth.map(|b| b.parse().unwrap()),
+            ..Identity::default()
+        });
+        contact.emails = Some(vec![Email { label: None, address: Some(email.to_string()) }]);
+        contact
+    }
+
+    fn sample_data() -> TuppData {
+        let ada = contact("Ada", "Lovelace", "ada@oldcorp.com", Some("1815-12-10"));
+        let alan = contact("Alan", "Turing", "alan@example.org", Some("1989-0-0"));
+        let grace = contact("Grace", "Hopper", "grace@oldcorp.com", Some("1990-0-0"));
+        let linus = contact("Linus", "Torvalds", "linus@example.org", None);
+
+        let mut family = Group::new("Family".to_string());
+        let cousins = Group::new("Cousins".to_string());
+        let mut data = TuppData::new();
+        data.contacts = vec![ada, alan, grace, linus];
+        data.contacts[0].groups = Some(HashSet::from([family.identifier]));
+        data.contacts[1].groups = Some(HashSet::from([cousins.identifier]));
+        family.subgroups.push(cousins);
+        data.groups = vec![family, Group::new("Work".to_string())];
+        data
+    }
+
+    /// The first names of the contacts matching the query.
+    fn matching(data: &TuppData, query: &str) -> Vec<String> {
+        let query = Query::parse(query).unwrap();
+        data.contacts.iter()
+            .filter(|c| query.matches(c, data))
+            .filter_map(|c| c.identity.first_name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn groups_match_by_substring_including_subgroups() {
+        let data = sample_data();
+        assert_eq!(matching(&data, "group:fam"), ["Ada", "Alan"]);
+        assert_eq!(matching(&data, "group:cous"), ["Alan"]);
+        assert_eq!(matching(&data, "group=fam"), Vec::<String>::new());
+        assert_eq!(matching(&data, "group=Family"), ["Ada", "Alan"]);
+        assert_eq!(matching(&data, "group:f*y"), ["Ada", "Alan"]);
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let data = sample_data();
+        assert_eq!(matching(&data, "first:ada OR first:alan AND last:hopper"), ["Ada"]);
+        assert_eq!(matching(&data, "(first:ada OR first:grace) AND email:oldcorp"), ["Ada", "Grace"]);
+    }
+
+    #[test]
+    fn adjacent_terms_are_joined_by_and() {
+        let data = sample_data();
+        assert_eq!(matching(&data, "email:oldcorp first:grace"), ["Grace"]);
+        assert_eq!(matching(&data, "al tur"), ["Alan"]);
+    }
+
+    #[test]
+    fn terms_can_be_negated() {
+        let data = sample_data();
+        assert_eq!(matching(&data, "NOT email:oldcorp"), ["Alan", "Linus"]);
+        assert_eq!(matching(&data, "-email:oldcorp -first:linus"), ["Alan"]);
+        assert_eq!(matching(&data, "NOT (first:ada OR first:alan)"), ["Grace", "Linus"]);
+    }
+
+    #[test]
+    fn wildcards_match_the_whole_value() {
+        let data = sample_data();
+        assert_eq!(matching(&data, "email:*@oldcorp.com"), ["Ada", "Grace"]);
+        assert_eq!(matching(&data, "email:*@oldcorp"), Vec::<String>::new());
+        assert_eq!(matching(&data, "first:?da"), ["Ada"]);
+    }
+
+    #[test]
+    fn partial_dates_compare_on_the_parts_they_have() {
+        let data = sample_data();
+        // Alan was born some time in 1989, Grace some time in 1990
+        assert_eq!(matching(&data, "birth<1990-06"), ["Ada", "Alan"]);
+        assert_eq!(matching(&data, "birth>=1990"), ["Grace"]);
+        assert_eq!(matching(&data, "birth>1989-06"), ["Grace"]);
+        assert_eq!(matching(&data, "birth.month:12"), ["Ada"]);
+        assert_eq!(matching(&data, "birth:1815-12"), ["Ada"]);
+    }
+}
@@ -1,3 +1,6 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
 pub fn trim_extra_spaces(input: &str) -> String {
     let trimmed = input.trim();
     let mut result = String::new();
@@ -11,4 +14,22 @@ pub fn trim_extra_spaces(input: &str) -> String {
     }
 
     result
-}
\ No newline at end of file
+}
+
+/// Fold text for comparison: extra spaces trimmed, lowercased, and
+/// diacritics stripped, so that "  Hélène " and "helene" are equal.
+pub fn fold(input: &str) -> String {
+    let mut result = String::new();
+
+    for c in trim_extra_spaces(input).nfkd() {
+        match c {
+            _ if is_combining_mark(c) => {},
+            'œ' | 'Œ' => result.push_str("oe"),
+            'æ' | 'Æ' => result.push_str("ae"),
+            'ß' => result.push_str("ss"),
+            _ => result.extend(c.to_lowercase()),
+        }
+    }
+
+    result
+}
@@ -0,0 +1,79 @@
+use std::cmp::Ordering;
+use crate::contact::Contact;
+use crate::sanitize::fold;
+
+/// A key to sort contacts on.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Last,
+    First,
+    Middle,
+    Title,
+    Birth,
+    Created,
+    Updated,
+}
+
+/// Sort contacts on the given keys, in order, ignoring case and accents for names.
+/// Contacts missing a value sort last, even when `reverse` is set.
+pub fn sort_contacts(contacts: &mut [&Contact], keys: &[SortKey], reverse: bool) {
+    contacts.sort_by(|a, b| {
+        keys.iter()
+            .map(|key| compare(a, b, *key, reverse))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// The uppercase initial of the first name-like sort key (the last name by
+/// default), used for grouping headers.
+pub fn initial(contact: &Contact, keys: &[SortKey]) -> char {
+    let key = keys.iter()
+        .find(|k| matches!(k, SortKey::Last | SortKey::First | SortKey::Middle | SortKey::Title))
+        .copied()
+        .unwrap_or(SortKey::Last);
+
+    name(contact, key)
+        .and_then(|n| fold(n).chars().next())
+        .filter(|c| c.is_alphabetic())
+        .map(|c| c.to_ascii_uppercase())
+        .unwrap_or('#')
+}
+
+fn name(contact: &Contact, key: SortKey) -> Option<&str> {
+    let identity = &contact.identity;
+    match key {
+        SortKey::Last => identity.last_name.as_deref(),
+        SortKey::First => identity.first_name.as_deref(),
+        SortKey::Middle => identity.middle_name.as_deref(),
+        SortKey::Title => identity.title.as_deref(),
+        _ => None,
+    }
+    .filter(|n| !n.trim().is_empty())
+}
+
+fn compare(a: &Contact, b: &Contact, key: SortKey, reverse: bool) -> Ordering {
+    match key {
+        SortKey::Birth => {
+            let date = |c: &Contact| c.identity.birth_date.as_ref().map(|d| (d.year, d.month, d.day));
+            ordered(date(a), date(b), reverse)
+        },
+        SortKey::Created => ordered(a.created, b.created, reverse),
+        SortKey::Updated => ordered(a.updated.or(a.created), b.updated.or(b.created), reverse),
+        name_key => {
+            // Collate on folded text first, so that "Élodie" sorts among the E's
+            let collation_key = |c: &Contact| name(c, name_key).map(|n| (fold(n), n.to_string()));
+            ordered(collation_key(a), collation_key(b), reverse)
+        },
+    }
+}
+
+fn ordered<T: Ord>(a: Option<T>, b: Option<T>, reverse: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if reverse => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
@@ -1,4 +1,3 @@
-use dirs;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::PathBuf;
@@ -51,9 +50,7 @@ pub fn save_data(path: &PathBuf, data: &TuppData) -> Result<(), TuppError> {
     // Validate data structure against schema
     let json_value = serde_json::to_value(data).map_err(TuppError::Serialization)?;
 
-    if let Err(e) = validation::validate_json(&json_value) {
-        return Err(e);
-    }
+    validation::validate_json(&json_value)?;
     
     let json_data = serde_json::to_string_pretty(&json_value).map_err(TuppError::Serialization)?;
     fs::write(path, json_data).map_err(TuppError::Io)